@@ -0,0 +1,113 @@
+/// The language variant the interpreter follows. The dialect provides the
+/// defaults for the policies that are not set explicitly.
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub enum Dialect {
+    #[default]
+    Befunge93,
+    /// Funge-98: division by zero pushes zero, `&` and `~` reflect at the
+    /// end of the input and unknown instructions reflect.
+    Funge98,
+}
+
+impl Dialect {
+    pub fn division_by_zero(self) -> DivisionByZero {
+        match self {
+            Dialect::Befunge93 => DivisionByZero::Prompt,
+            Dialect::Funge98 => DivisionByZero::PushZero,
+        }
+    }
+
+    pub fn end_of_input(self) -> EndOfInput {
+        match self {
+            Dialect::Befunge93 => EndOfInput::PushMinusOne,
+            Dialect::Funge98 => EndOfInput::Reflect,
+        }
+    }
+
+    pub fn unknown_instruction(self) -> UnknownInstruction {
+        match self {
+            Dialect::Befunge93 => UnknownInstruction::Error,
+            Dialect::Funge98 => UnknownInstruction::Reflect,
+        }
+    }
+}
+
+/// What `/` and `%` do when the divisor is zero.
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub enum DivisionByZero {
+    /// Ask the user for the result, as the Befunge-93 specification requires.
+    #[default]
+    Prompt,
+    /// Push zero.
+    PushZero,
+    /// Stop with [`InterpreterError::DivisionByZero`](crate::InterpreterError::DivisionByZero).
+    Error,
+}
+
+/// How `+`, `-`, `*`, `/` and `%` handle results that do not fit in a cell.
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub enum Overflow {
+    /// Wrap around in two's complement, like the reference interpreter.
+    #[default]
+    Wrapping,
+    /// Clamp to the smallest or largest cell value.
+    Saturating,
+    /// Stop with [`InterpreterError::Overflow`](crate::InterpreterError::Overflow).
+    Error,
+}
+
+/// What `&` and `~` do when there is no more input to read.
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub enum EndOfInput {
+    /// Push -1.
+    #[default]
+    PushMinusOne,
+    /// Reverse the direction of the pointer, as Funge-98 does.
+    Reflect,
+}
+
+/// How `~` splits the input into characters.
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub enum InputEncoding {
+    /// Decode UTF-8, pushing the code point of each character.
+    #[default]
+    Utf8,
+    /// Push each byte on its own.
+    Bytes,
+}
+
+/// How characters outside the playfield are handled when loading a program.
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub enum LoadMode {
+    /// Refuse to load the program.
+    #[default]
+    Strict,
+    /// Drop the characters and record a [`Warning`](crate::Warning).
+    Truncate,
+}
+
+/// What `p` and `g` do with coordinates outside the playfield.
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub enum OutOfBounds {
+    /// `p` does nothing and `g` pushes zero.
+    #[default]
+    Ignore,
+    /// Wrap the coordinates around the edges of the playfield.
+    Wrap,
+    /// Stop with [`InterpreterError::OutOfBounds`](crate::InterpreterError::OutOfBounds).
+    Error,
+}
+
+/// What happens when the pointer meets a character that is not an instruction.
+/// Outside of `Error`, each unknown character is recorded once per location as
+/// a [`Warning`](crate::Warning).
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub enum UnknownInstruction {
+    /// Stop with [`InterpreterError::UnknownInstruction`](crate::InterpreterError::UnknownInstruction).
+    #[default]
+    Error,
+    /// Skip the character as if it were a space.
+    Ignore,
+    /// Reverse the direction of the pointer.
+    Reflect,
+}
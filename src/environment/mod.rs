@@ -0,0 +1,136 @@
+use std::env;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// What the `y` instruction reports about the world outside the program.
+/// Time defaults to the clock of the process and can be pinned to make runs
+/// reproducible. No variables are reported unless they are pinned or those
+/// of the process are explicitly inherited, as they may hold secrets.
+#[derive(Debug, Default, Clone)]
+pub struct Environment {
+    pub time: Option<SystemTime>,
+    pub variables: Option<Vec<(String, String)>>,
+    pub inherit_variables: bool,
+    pub arguments: Vec<String>,
+}
+
+impl Environment {
+    /// The date and time cells of `y`, in UTC:
+    /// `(year - 1900) * 256 * 256 + month * 256 + day` and
+    /// `hour * 256 * 256 + minute * 256 + second`.
+    pub fn date_and_time(&self) -> (i32, i32) {
+        let time = self.time.unwrap_or_else(SystemTime::now);
+        let seconds = match time.duration_since(UNIX_EPOCH) {
+            Ok(elapsed) => elapsed.as_secs() as i64,
+            Err(error) => -(error.duration().as_secs() as i64),
+        };
+        let (year, month, day) = civil_from_days(seconds.div_euclid(86_400));
+        let of_day = seconds.rem_euclid(86_400);
+        let (hour, minute, second) = (of_day / 3600, of_day / 60 % 60, of_day % 60);
+        (
+            ((year - 1900) * 256 * 256 + month * 256 + day) as i32,
+            (hour * 256 * 256 + minute * 256 + second) as i32,
+        )
+    }
+
+    /// The environment variables as `(name, value)` pairs. Inherited
+    /// variables that are not valid Unicode are converted lossily.
+    pub fn variables(&self) -> Vec<(String, String)> {
+        match &self.variables {
+            Some(variables) => variables.clone(),
+            None if self.inherit_variables => env::vars_os()
+                .map(|(name, value)| {
+                    (
+                        name.to_string_lossy().into_owned(),
+                        value.to_string_lossy().into_owned(),
+                    )
+                })
+                .collect(),
+            None => vec![],
+        }
+    }
+}
+
+/// Converts days since 1970-01-01 to a `(year, month, day)` date of the
+/// proleptic Gregorian calendar.
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn at(seconds: u64) -> Environment {
+        Environment {
+            time: Some(UNIX_EPOCH + Duration::from_secs(seconds)),
+            ..Environment::default()
+        }
+    }
+
+    #[test]
+    fn civil_from_days_epoch() {
+        assert_eq!((1970, 1, 1), civil_from_days(0));
+        assert_eq!((1969, 12, 31), civil_from_days(-1));
+        assert_eq!((2000, 2, 29), civil_from_days(11_016));
+        assert_eq!((2024, 3, 1), civil_from_days(19_783));
+    }
+
+    #[test]
+    fn date_and_time() {
+        let (date, time) = at(951_827_696).date_and_time();
+        assert_eq!((100 << 16) + (2 << 8) + 29, date);
+        assert_eq!((12 << 16) + (34 << 8) + 56, time);
+    }
+
+    #[test]
+    fn date_and_time_before_epoch() {
+        let environment = Environment {
+            time: Some(UNIX_EPOCH - Duration::from_secs(1)),
+            ..Environment::default()
+        };
+        let (date, time) = environment.date_and_time();
+        assert_eq!((69 << 16) + (12 << 8) + 31, date);
+        assert_eq!((23 << 16) + (59 << 8) + 59, time);
+    }
+
+    #[test]
+    fn variables_pinned() {
+        let environment = Environment {
+            variables: Some(vec![("A".to_string(), "1".to_string())]),
+            ..Environment::default()
+        };
+        assert_eq!(
+            vec![("A".to_string(), "1".to_string())],
+            environment.variables()
+        );
+    }
+
+    #[test]
+    fn variables_hidden_by_default() {
+        assert!(Environment::default().variables().is_empty());
+    }
+
+    #[test]
+    fn variables_inherited() {
+        let environment = Environment {
+            inherit_variables: true,
+            ..Environment::default()
+        };
+        assert_eq!(env::vars_os().count(), environment.variables().len());
+    }
+}
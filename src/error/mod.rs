@@ -0,0 +1,275 @@
+use std::{error::Error, fmt, io};
+
+#[derive(Debug)]
+pub enum InterpreterError {
+    UnknownInstruction { command: char, x: i32, y: i32 },
+    InvalidInput(String),
+    EndOfInput { command: char, dividend: i32 },
+    Io(io::Error),
+    InvalidCharacter(i32),
+    DivisionByZero { command: char, x: i32, y: i32 },
+    Overflow { command: char, x: i32, y: i32 },
+    OutOfBounds { command: char, x: i32, y: i32 },
+    ProgramEnded,
+}
+
+impl fmt::Display for InterpreterError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InterpreterError::UnknownInstruction { command, x, y } => {
+                write!(f, "Unknown command '{}' at ({}, {})", command, x, y)
+            }
+            InterpreterError::InvalidInput(input) => write!(f, "Invalid input '{}'", input),
+            InterpreterError::EndOfInput { command, dividend } => write!(
+                f,
+                "End of input while asking for the result of {}{}0",
+                dividend, command
+            ),
+            InterpreterError::Io(error) => write!(f, "I/O error: {}", error),
+            InterpreterError::InvalidCharacter(value) => {
+                write!(f, "Value {} is not a valid character", value)
+            }
+            InterpreterError::DivisionByZero { command, x, y } => {
+                write!(f, "Division by zero in '{}' at ({}, {})", command, x, y)
+            }
+            InterpreterError::Overflow { command, x, y } => {
+                write!(f, "Integer overflow in '{}' at ({}, {})", command, x, y)
+            }
+            InterpreterError::OutOfBounds { command, x, y } => write!(
+                f,
+                "'{}' accessed ({}, {}), outside the playfield",
+                command, x, y
+            ),
+            InterpreterError::ProgramEnded => {
+                write!(f, "Cannot execute further on an ended program")
+            }
+        }
+    }
+}
+
+impl Error for InterpreterError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            InterpreterError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for InterpreterError {
+    fn from(error: io::Error) -> Self {
+        InterpreterError::Io(error)
+    }
+}
+
+/// Why a program could not be loaded.
+#[derive(Debug)]
+pub enum LoadError {
+    /// A character lies past the edges of the playfield. Lines and columns
+    /// are numbered from one.
+    OutsidePlayfield {
+        line: usize,
+        column: usize,
+    },
+    Io(io::Error),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::OutsidePlayfield { line, column } => write!(
+                f,
+                "Character at line {}, column {} is outside the playfield",
+                line, column
+            ),
+            LoadError::Io(error) => write!(f, "I/O error: {}", error),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(error: io::Error) -> Self {
+        LoadError::Io(error)
+    }
+}
+
+/// An invalid [`InterpreterBuilder`](crate::InterpreterBuilder) configuration.
+#[derive(Debug, PartialEq)]
+pub enum ConfigError {
+    InvalidDimensions {
+        width: usize,
+        height: usize,
+    },
+    /// Two options that cannot be set together.
+    Conflict(&'static str, &'static str),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::InvalidDimensions { width, height } => {
+                write!(f, "Invalid playfield dimensions {}x{}", width, height)
+            }
+            ConfigError::Conflict(first, second) => {
+                write!(f, "Options '{}' and '{}' cannot be combined", first, second)
+            }
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+/// Why [`InterpreterBuilder::build`](crate::InterpreterBuilder::build) failed.
+#[derive(Debug)]
+pub enum BuildError {
+    Config(ConfigError),
+    Load(LoadError),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BuildError::Config(error) => write!(f, "{}", error),
+            BuildError::Load(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl Error for BuildError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            BuildError::Config(error) => Some(error),
+            BuildError::Load(error) => Some(error),
+        }
+    }
+}
+
+impl From<ConfigError> for BuildError {
+    fn from(error: ConfigError) -> Self {
+        BuildError::Config(error)
+    }
+}
+
+impl From<LoadError> for BuildError {
+    fn from(error: LoadError) -> Self {
+        BuildError::Load(error)
+    }
+}
+
+/// A problem that does not stop the program. Lines and columns are
+/// numbered from one.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Warning {
+    /// The line was cut at the given column while loading.
+    Truncated { line: usize, column: usize },
+    /// The pointer met a character that is not an instruction. Coordinates
+    /// are those of the playfield, numbered from zero.
+    UnknownInstruction { command: char, x: i32, y: i32 },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Warning::Truncated { line, column } => write!(
+                f,
+                "Line {} truncated at column {}, outside the playfield",
+                line, column
+            ),
+            Warning::UnknownInstruction { command, x, y } => {
+                write!(f, "Unknown command '{}' at ({}, {})", command, x, y)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display_unknown_instruction() {
+        let error = InterpreterError::UnknownInstruction {
+            command: 'A',
+            x: 1,
+            y: 2,
+        };
+        assert_eq!("Unknown command 'A' at (1, 2)", error.to_string());
+    }
+
+    #[test]
+    fn display_end_of_input() {
+        let error = InterpreterError::EndOfInput {
+            command: '%',
+            dividend: 5,
+        };
+        assert_eq!(
+            "End of input while asking for the result of 5%0",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn display_invalid_character() {
+        let error = InterpreterError::InvalidCharacter(-1);
+        assert_eq!("Value -1 is not a valid character", error.to_string());
+    }
+
+    #[test]
+    fn from_io_error() {
+        let error = InterpreterError::from(io::Error::other("broken"));
+        assert!(error.source().is_some());
+        assert_eq!("I/O error: broken", error.to_string());
+    }
+
+    #[test]
+    fn display_load_error() {
+        let error = LoadError::OutsidePlayfield {
+            line: 26,
+            column: 1,
+        };
+        assert_eq!(
+            "Character at line 26, column 1 is outside the playfield",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn display_config_error() {
+        let error = ConfigError::Conflict("seed", "direction_source");
+        assert_eq!(
+            "Options 'seed' and 'direction_source' cannot be combined",
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn display_warning() {
+        let warning = Warning::Truncated {
+            line: 3,
+            column: 81,
+        };
+        assert_eq!(
+            "Line 3 truncated at column 81, outside the playfield",
+            warning.to_string()
+        );
+    }
+
+    #[test]
+    fn display_warning_unknown_instruction() {
+        let warning = Warning::UnknownInstruction {
+            command: 'A',
+            x: 4,
+            y: 2,
+        };
+        assert_eq!("Unknown command 'A' at (4, 2)", warning.to_string());
+    }
+}
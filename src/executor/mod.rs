@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::process::Command;
+
+/// Runs the commands given to the Funge-98 `=` instruction.
+pub trait Executor: Debug {
+    /// Runs `command` and returns its exit status, or `None` when it cannot
+    /// be run, which makes `=` reflect.
+    fn execute(&mut self, command: &str) -> Option<i32>;
+
+    /// The operating paradigm reported by `y`: 0 when `=` is unavailable,
+    /// 1 when commands behave like C's `system()`.
+    fn paradigm(&self) -> i32;
+}
+
+/// Refuses every command, so that untrusted programs cannot reach the system.
+#[derive(Debug, Default)]
+pub struct DisabledExecutor;
+
+impl Executor for DisabledExecutor {
+    fn execute(&mut self, _command: &str) -> Option<i32> {
+        None
+    }
+
+    fn paradigm(&self) -> i32 {
+        0
+    }
+}
+
+/// Runs commands through the system shell, `sh -c` or `cmd /C` on Windows.
+/// Commands write directly to the standard streams of the process.
+#[derive(Debug, Default)]
+pub struct SystemShell;
+
+impl Executor for SystemShell {
+    fn execute(&mut self, command: &str) -> Option<i32> {
+        let (shell, flag) = if cfg!(windows) {
+            ("cmd", "/C")
+        } else {
+            ("sh", "-c")
+        };
+        let status = Command::new(shell).arg(flag).arg(command).status().ok()?;
+        Some(status.code().unwrap_or(-1))
+    }
+
+    fn paradigm(&self) -> i32 {
+        1
+    }
+}
+
+/// Answers known commands with fixed exit statuses, without running
+/// anything. Unknown commands cannot be run.
+#[derive(Debug)]
+pub struct ScriptedExecutor {
+    statuses: HashMap<String, i32>,
+    commands: Vec<String>,
+}
+
+impl ScriptedExecutor {
+    pub fn new(statuses: Vec<(String, i32)>) -> Self {
+        ScriptedExecutor {
+            statuses: statuses.into_iter().collect(),
+            commands: vec![],
+        }
+    }
+
+    /// Every command received so far, in order.
+    pub fn commands(&self) -> &[String] {
+        &self.commands
+    }
+}
+
+impl Executor for ScriptedExecutor {
+    fn execute(&mut self, command: &str) -> Option<i32> {
+        self.commands.push(command.to_string());
+        self.statuses.get(command).copied()
+    }
+
+    fn paradigm(&self) -> i32 {
+        1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disabled() {
+        let mut executor = DisabledExecutor;
+        assert_eq!(None, executor.execute("true"));
+        assert_eq!(0, executor.paradigm());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn system_shell() {
+        let mut executor = SystemShell;
+        assert_eq!(Some(0), executor.execute("true"));
+        assert_eq!(Some(3), executor.execute("exit 3"));
+        assert_eq!(1, executor.paradigm());
+    }
+
+    #[test]
+    fn scripted() {
+        let mut executor = ScriptedExecutor::new(vec![("make".to_string(), 2)]);
+        assert_eq!(Some(2), executor.execute("make"));
+        assert_eq!(None, executor.execute("rm -rf /"));
+        assert_eq!(&["make", "rm -rf /"], executor.commands());
+    }
+}
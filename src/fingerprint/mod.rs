@@ -0,0 +1,202 @@
+use crate::error::InterpreterError;
+use crate::ip::Ip;
+use crate::playfield::Playfield;
+use std::fmt::Debug;
+
+/// The behaviour of an `A`-`Z` instruction loaded from a fingerprint, run
+/// on the current IP.
+pub type Semantic = fn(&mut Ip, &mut Playfield) -> Result<(), InterpreterError>;
+
+/// A Funge-98 semantic extension, loaded with `(` and unloaded with `)`.
+pub trait Fingerprint: Debug {
+    /// The id requested by programs, usually the name read as base-256 digits.
+    fn id(&self) -> i32;
+    /// The instructions the fingerprint defines, with their semantics.
+    fn semantics(&self) -> Vec<(char, Semantic)>;
+}
+
+/// Computes the id of a fingerprint from its name, the way `(` does when the
+/// name is pushed as a string.
+pub fn fingerprint_id(name: &str) -> i32 {
+    name.bytes().fold(0, |id: i32, byte| {
+        id.wrapping_mul(256).wrapping_add(i32::from(byte))
+    })
+}
+
+/// The `NULL` fingerprint: every instruction from `A` to `Z` reflects.
+#[derive(Debug)]
+pub struct Null;
+
+impl Fingerprint for Null {
+    fn id(&self) -> i32 {
+        fingerprint_id("NULL")
+    }
+
+    fn semantics(&self) -> Vec<(char, Semantic)> {
+        (b'A'..=b'Z')
+            .map(|letter| (char::from(letter), reflect as Semantic))
+            .collect()
+    }
+}
+
+fn reflect(ip: &mut Ip, _: &mut Playfield) -> Result<(), InterpreterError> {
+    ip.pointer.reflect();
+    Ok(())
+}
+
+/// The fingerprints programs can load, by id. `NULL` is always available.
+#[derive(Debug)]
+pub struct FingerprintRegistry {
+    fingerprints: Vec<Box<dyn Fingerprint>>,
+}
+
+impl Default for FingerprintRegistry {
+    fn default() -> Self {
+        FingerprintRegistry {
+            fingerprints: vec![Box::new(Null)],
+        }
+    }
+}
+
+impl FingerprintRegistry {
+    /// Adds a fingerprint, replacing any other with the same id.
+    pub fn register(&mut self, fingerprint: Box<dyn Fingerprint>) {
+        let id = fingerprint.id();
+        self.fingerprints.retain(|registered| registered.id() != id);
+        self.fingerprints.push(fingerprint);
+    }
+
+    pub fn get(&self, id: i32) -> Option<&dyn Fingerprint> {
+        self.fingerprints
+            .iter()
+            .find(|fingerprint| fingerprint.id() == id)
+            .map(|fingerprint| fingerprint.as_ref())
+    }
+}
+
+/// The semantics loaded by an IP: one stack per instruction from `A` to `Z`,
+/// the top of which runs when the instruction is executed.
+#[derive(Debug, Clone)]
+pub struct Semantics {
+    stacks: Vec<Vec<Semantic>>,
+}
+
+impl Default for Semantics {
+    fn default() -> Self {
+        Semantics {
+            stacks: vec![vec![]; 26],
+        }
+    }
+}
+
+impl Semantics {
+    /// The semantic the instruction currently has, if any was loaded.
+    pub fn get(&self, instruction: char) -> Option<Semantic> {
+        Semantics::index(instruction).and_then(|index| self.stacks[index].last().copied())
+    }
+
+    /// Pushes the semantics of a fingerprint over the ones already loaded.
+    pub fn load(&mut self, fingerprint: &dyn Fingerprint) {
+        for (instruction, semantic) in fingerprint.semantics() {
+            if let Some(index) = Semantics::index(instruction) {
+                self.stacks[index].push(semantic);
+            }
+        }
+    }
+
+    /// Pops one semantic for each instruction the fingerprint defines,
+    /// whichever fingerprint it came from.
+    pub fn unload(&mut self, fingerprint: &dyn Fingerprint) {
+        for (instruction, _) in fingerprint.semantics() {
+            if let Some(index) = Semantics::index(instruction) {
+                self.stacks[index].pop();
+            }
+        }
+    }
+
+    fn index(instruction: char) -> Option<usize> {
+        if instruction.is_ascii_uppercase() {
+            Some(instruction as usize - 'A' as usize)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test_support {
+    use super::*;
+
+    /// A fingerprint for tests, `PUSH`, whose `P` pushes 7.
+    #[derive(Debug)]
+    pub(crate) struct Push;
+
+    impl Fingerprint for Push {
+        fn id(&self) -> i32 {
+            fingerprint_id("PUSH")
+        }
+
+        fn semantics(&self) -> Vec<(char, Semantic)> {
+            vec![('P', push_seven as Semantic)]
+        }
+    }
+
+    fn push_seven(ip: &mut Ip, _: &mut Playfield) -> Result<(), InterpreterError> {
+        ip.stacks_mut().push(7);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_support::Push;
+    use super::*;
+    use crate::pointer::Direction;
+
+    #[test]
+    fn id() {
+        assert_eq!(0x4E55_4C4C, fingerprint_id("NULL"));
+        assert_eq!(0x4E55_4C4C, Null.id());
+    }
+
+    #[test]
+    fn null_reflects() {
+        let semantics = Null.semantics();
+        assert_eq!(26, semantics.len());
+        let mut ip = Ip::new(0);
+        let mut playfield = Playfield::unbounded();
+        for (_, semantic) in semantics {
+            semantic(&mut ip, &mut playfield).unwrap();
+        }
+        assert_eq!(Some(Direction::Right), ip.pointer().direction());
+    }
+
+    #[test]
+    fn registry() {
+        let mut registry = FingerprintRegistry::default();
+        assert!(registry.get(fingerprint_id("NULL")).is_some());
+        assert!(registry.get(fingerprint_id("PUSH")).is_none());
+        registry.register(Box::new(Push));
+        assert!(registry.get(fingerprint_id("PUSH")).is_some());
+        registry.register(Box::new(Push));
+        assert_eq!(2, registry.fingerprints.len());
+    }
+
+    #[test]
+    fn semantics_load_unload() {
+        let mut semantics = Semantics::default();
+        assert!(semantics.get('P').is_none());
+        semantics.load(&Null);
+        semantics.load(&Push);
+        let mut ip = Ip::new(0);
+        let mut playfield = Playfield::unbounded();
+        semantics.get('P').unwrap()(&mut ip, &mut playfield).unwrap();
+        assert_eq!(vec![7], ip.stack().values());
+        semantics.unload(&Push);
+        semantics.get('P').unwrap()(&mut ip, &mut playfield).unwrap();
+        assert_eq!(Some(Direction::Left), ip.pointer().direction());
+        semantics.unload(&Null);
+        assert!(semantics.get('P').is_none());
+        assert!(semantics.get('a').is_none());
+    }
+}
@@ -0,0 +1,197 @@
+use crate::config::InputEncoding;
+use crate::error::InterpreterError;
+use std::io::{self, BufRead};
+
+/// Buffered program input shared by `&` and `~`, so that each instruction
+/// consumes only what it needs and leaves the rest for the next one.
+#[derive(Debug)]
+pub struct Input<R: BufRead> {
+    reader: R,
+}
+
+impl<R: BufRead> Input<R> {
+    pub fn new(reader: R) -> Self {
+        Input { reader }
+    }
+
+    /// Reads a single character. Returns `None` at the end of the input.
+    pub fn read_char(&mut self, encoding: InputEncoding) -> io::Result<Option<i32>> {
+        match encoding {
+            InputEncoding::Bytes => Ok(self.next_byte()?.map(i32::from)),
+            InputEncoding::Utf8 => self.read_utf8(),
+        }
+    }
+
+    /// Skips everything up to the first digit and reads the number starting
+    /// there, leaving whatever follows it in the input. A `-` right before
+    /// the first digit makes the number negative. Returns `None` at the end
+    /// of the input.
+    pub fn read_number(&mut self) -> Result<Option<i32>, InterpreterError> {
+        let mut negative = false;
+        loop {
+            match self.peek_byte()? {
+                None => return Ok(None),
+                Some(byte) if byte.is_ascii_digit() => break,
+                Some(byte) => {
+                    negative = byte == b'-';
+                    self.reader.consume(1);
+                }
+            }
+        }
+        let mut number = String::new();
+        if negative {
+            number.push('-');
+        }
+        while let Some(byte) = self.peek_byte()? {
+            if !byte.is_ascii_digit() {
+                break;
+            }
+            number.push(char::from(byte));
+            self.reader.consume(1);
+        }
+        number
+            .parse::<i32>()
+            .map(Some)
+            .map_err(|_| InterpreterError::InvalidInput(number))
+    }
+
+    /// Decodes one UTF-8 character, yielding U+FFFD for malformed sequences.
+    fn read_utf8(&mut self) -> io::Result<Option<i32>> {
+        let first = match self.next_byte()? {
+            Some(byte) => byte,
+            None => return Ok(None),
+        };
+        let width = match first {
+            0x00..=0x7F => 1,
+            0xC0..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF7 => 4,
+            _ => return Ok(Some(char::REPLACEMENT_CHARACTER as i32)),
+        };
+        let mut bytes = vec![first];
+        while bytes.len() < width {
+            match self.peek_byte()? {
+                Some(byte) if byte & 0xC0 == 0x80 => {
+                    bytes.push(byte);
+                    self.reader.consume(1);
+                }
+                _ => return Ok(Some(char::REPLACEMENT_CHARACTER as i32)),
+            }
+        }
+        let read_char = std::str::from_utf8(&bytes)
+            .ok()
+            .and_then(|decoded| decoded.chars().next())
+            .unwrap_or(char::REPLACEMENT_CHARACTER);
+        Ok(Some(read_char as i32))
+    }
+
+    fn peek_byte(&mut self) -> io::Result<Option<u8>> {
+        Ok(self.reader.fill_buf()?.first().copied())
+    }
+
+    fn next_byte(&mut self) -> io::Result<Option<u8>> {
+        let byte = self.peek_byte()?;
+        if byte.is_some() {
+            self.reader.consume(1);
+        }
+        Ok(byte)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_input(input: &str) -> Input<&[u8]> {
+        Input::new(input.as_bytes())
+    }
+
+    #[test]
+    fn read_char() {
+        let mut input = new_input("ab");
+        assert_eq!(
+            Some('a' as i32),
+            input.read_char(InputEncoding::Utf8).unwrap()
+        );
+        assert_eq!(
+            Some('b' as i32),
+            input.read_char(InputEncoding::Utf8).unwrap()
+        );
+        assert_eq!(None, input.read_char(InputEncoding::Utf8).unwrap());
+    }
+
+    #[test]
+    fn read_char_keeps_newlines() {
+        let mut input = new_input("a\nb");
+        assert_eq!(
+            Some('a' as i32),
+            input.read_char(InputEncoding::Utf8).unwrap()
+        );
+        assert_eq!(
+            Some('\n' as i32),
+            input.read_char(InputEncoding::Utf8).unwrap()
+        );
+        assert_eq!(
+            Some('b' as i32),
+            input.read_char(InputEncoding::Utf8).unwrap()
+        );
+    }
+
+    #[test]
+    fn read_char_utf8() {
+        let mut input = new_input("é€");
+        assert_eq!(
+            Some('é' as i32),
+            input.read_char(InputEncoding::Utf8).unwrap()
+        );
+        assert_eq!(
+            Some('€' as i32),
+            input.read_char(InputEncoding::Utf8).unwrap()
+        );
+    }
+
+    #[test]
+    fn read_char_bytes() {
+        let mut input = new_input("é");
+        assert_eq!(Some(0xC3), input.read_char(InputEncoding::Bytes).unwrap());
+        assert_eq!(Some(0xA9), input.read_char(InputEncoding::Bytes).unwrap());
+        assert_eq!(None, input.read_char(InputEncoding::Bytes).unwrap());
+    }
+
+    #[test]
+    fn read_char_malformed_utf8() {
+        let mut input = Input::new(&[0xC3, b'a'][..]);
+        assert_eq!(
+            Some(char::REPLACEMENT_CHARACTER as i32),
+            input.read_char(InputEncoding::Utf8).unwrap()
+        );
+        assert_eq!(
+            Some('a' as i32),
+            input.read_char(InputEncoding::Utf8).unwrap()
+        );
+    }
+
+    #[test]
+    fn read_number() {
+        let mut input = new_input("12 34");
+        assert_eq!(Some(12), input.read_number().unwrap());
+        assert_eq!(Some(34), input.read_number().unwrap());
+        assert_eq!(None, input.read_number().unwrap());
+    }
+
+    #[test]
+    fn read_number_leaves_rest() {
+        let mut input = new_input("x-7y");
+        assert_eq!(Some(-7), input.read_number().unwrap());
+        assert_eq!(
+            Some('y' as i32),
+            input.read_char(InputEncoding::Utf8).unwrap()
+        );
+    }
+
+    #[test]
+    fn read_number_too_large() {
+        let mut input = new_input("99999999999");
+        assert!(input.read_number().is_err());
+    }
+}
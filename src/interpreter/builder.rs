@@ -0,0 +1,589 @@
+use super::{Interpreter, Limits};
+use crate::config::*;
+use crate::environment::Environment;
+use crate::error::{BuildError, ConfigError, LoadError};
+use crate::executor::{DisabledExecutor, Executor};
+use crate::fingerprint::{Fingerprint, FingerprintRegistry};
+use crate::input::Input;
+use crate::ip::Ip;
+use crate::playfield::Playfield;
+use crate::random::{DirectionSource, RandomDirections};
+use crate::sandbox::Sandbox;
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+/// Configures and loads an [`Interpreter`]. Options left unset take the
+/// defaults of the selected [`Dialect`].
+///
+/// ```
+/// use bfng::{InterpreterBuilder, Overflow};
+///
+/// let mut interpreter = InterpreterBuilder::new()
+///     .dimensions(120, 40)
+///     .overflow(Overflow::Saturating)
+///     .seed(42)
+///     .stdout(vec![])
+///     .build("\"!iH\",,,@".as_bytes())
+///     .unwrap();
+/// interpreter.run().unwrap();
+/// assert_eq!(b"Hi!", interpreter.output().as_slice());
+/// ```
+#[derive(Debug)]
+pub struct InterpreterBuilder<I, O> {
+    stdin: I,
+    stdout: O,
+    settings: Settings,
+}
+
+#[derive(Debug)]
+struct Settings {
+    dialect: Dialect,
+    dimensions: Option<(usize, usize)>,
+    load_mode: LoadMode,
+    seed: Option<u64>,
+    directions: Option<Box<dyn DirectionSource>>,
+    executor: Option<Box<dyn Executor>>,
+    fingerprints: FingerprintRegistry,
+    division_by_zero: Option<DivisionByZero>,
+    overflow: Option<Overflow>,
+    end_of_input: Option<EndOfInput>,
+    input_encoding: Option<InputEncoding>,
+    out_of_bounds: Option<OutOfBounds>,
+    unknown_instruction: Option<UnknownInstruction>,
+    limits: Limits,
+    environment: Environment,
+    sandbox: Option<Sandbox>,
+}
+
+impl Default for InterpreterBuilder<io::Empty, io::Sink> {
+    fn default() -> Self {
+        InterpreterBuilder {
+            stdin: io::empty(),
+            stdout: io::sink(),
+            settings: Settings {
+                dialect: Dialect::default(),
+                dimensions: None,
+                load_mode: LoadMode::default(),
+                seed: None,
+                directions: None,
+                executor: None,
+                fingerprints: FingerprintRegistry::default(),
+                division_by_zero: None,
+                overflow: None,
+                end_of_input: None,
+                input_encoding: None,
+                out_of_bounds: None,
+                unknown_instruction: None,
+                limits: Limits::default(),
+                environment: Environment::default(),
+                sandbox: None,
+            },
+        }
+    }
+}
+
+impl InterpreterBuilder<io::Empty, io::Sink> {
+    /// Starts from a Befunge-93 interpreter on an 80x25 playfield with no
+    /// input and discarded output.
+    pub fn new() -> Self {
+        InterpreterBuilder::default()
+    }
+}
+
+impl<I, O> InterpreterBuilder<I, O>
+where
+    I: BufRead,
+    O: Write,
+{
+    pub fn stdin<J: BufRead>(self, stdin: J) -> InterpreterBuilder<J, O> {
+        InterpreterBuilder {
+            stdin,
+            stdout: self.stdout,
+            settings: self.settings,
+        }
+    }
+
+    pub fn stdout<P: Write>(self, stdout: P) -> InterpreterBuilder<I, P> {
+        InterpreterBuilder {
+            stdin: self.stdin,
+            stdout,
+            settings: self.settings,
+        }
+    }
+
+    pub fn dialect(mut self, dialect: Dialect) -> Self {
+        self.settings.dialect = dialect;
+        self
+    }
+
+    /// Sets the size of the playfield. Both dimensions must be positive.
+    ///
+    /// Without dimensions, Befunge-93 uses an 80x25 playfield and Funge-98
+    /// an unbounded one; with them, both dialects use a fixed playfield.
+    pub fn dimensions(mut self, width: usize, height: usize) -> Self {
+        self.settings.dimensions = Some((width, height));
+        self
+    }
+
+    /// Selects whether programs that do not fit the playfield are refused
+    /// (strict) or truncated with a warning (permissive).
+    pub fn load_mode(mut self, load_mode: LoadMode) -> Self {
+        self.settings.load_mode = load_mode;
+        self
+    }
+
+    /// Seeds the directions chosen by `?`. Cannot be combined with
+    /// [`direction_source`](InterpreterBuilder::direction_source).
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.settings.seed = Some(seed);
+        self
+    }
+
+    pub fn direction_source(mut self, directions: Box<dyn DirectionSource>) -> Self {
+        self.settings.directions = Some(directions);
+        self
+    }
+
+    /// Sets the executor running the commands of the Funge-98 `=`
+    /// instruction. By default commands are refused and `=` reflects.
+    pub fn executor(mut self, executor: Box<dyn Executor>) -> Self {
+        self.settings.executor = Some(executor);
+        self
+    }
+
+    pub fn division_by_zero(mut self, division_by_zero: DivisionByZero) -> Self {
+        self.settings.division_by_zero = Some(division_by_zero);
+        self
+    }
+
+    pub fn overflow(mut self, overflow: Overflow) -> Self {
+        self.settings.overflow = Some(overflow);
+        self
+    }
+
+    pub fn end_of_input(mut self, end_of_input: EndOfInput) -> Self {
+        self.settings.end_of_input = Some(end_of_input);
+        self
+    }
+
+    pub fn input_encoding(mut self, input_encoding: InputEncoding) -> Self {
+        self.settings.input_encoding = Some(input_encoding);
+        self
+    }
+
+    pub fn out_of_bounds(mut self, out_of_bounds: OutOfBounds) -> Self {
+        self.settings.out_of_bounds = Some(out_of_bounds);
+        self
+    }
+
+    /// Selects whether unknown instructions stop the program (strict), are
+    /// skipped or reflect the pointer (permissive).
+    pub fn unknown_instruction(mut self, unknown_instruction: UnknownInstruction) -> Self {
+        self.settings.unknown_instruction = Some(unknown_instruction);
+        self
+    }
+
+    /// Sets the limits applied by [`Interpreter::run`].
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.settings.limits = limits;
+        self
+    }
+
+    /// Pins the date and time reported by `y` instead of reading the clock.
+    pub fn time(mut self, time: SystemTime) -> Self {
+        self.settings.environment.time = Some(time);
+        self
+    }
+
+    /// Pins the environment variables reported by `y`. By default none are
+    /// reported.
+    pub fn environment_variables(mut self, variables: Vec<(String, String)>) -> Self {
+        self.settings.environment.variables = Some(variables);
+        self
+    }
+
+    /// Lets `y` report the environment variables of the process, unless
+    /// they are pinned with [`environment_variables`](Self::environment_variables).
+    /// Only enable this for trusted programs, as variables may hold secrets.
+    pub fn inherit_environment_variables(mut self) -> Self {
+        self.settings.environment.inherit_variables = true;
+        self
+    }
+
+    /// Sets the command-line arguments reported by `y`, starting with the
+    /// name of the program.
+    pub fn arguments(mut self, arguments: Vec<String>) -> Self {
+        self.settings.environment.arguments = arguments;
+        self
+    }
+
+    /// Enables the Funge-98 file instructions `i` and `o`, confined to the
+    /// files below `root`. Without a sandbox both instructions reflect.
+    pub fn sandbox<P: Into<PathBuf>>(mut self, root: P) -> Self {
+        self.settings.sandbox = Some(Sandbox::new(root));
+        self
+    }
+
+    /// Makes a fingerprint available to the Funge-98 `(` instruction, next
+    /// to the built-in `NULL`.
+    pub fn fingerprint(mut self, fingerprint: Box<dyn Fingerprint>) -> Self {
+        self.settings.fingerprints.register(fingerprint);
+        self
+    }
+
+    /// Checks the configuration and loads the program line by line from `source`.
+    pub fn build<S: BufRead>(self, source: S) -> Result<Interpreter<I, O>, BuildError> {
+        self.settings.validate()?;
+        Ok(self.load(source)?)
+    }
+
+    /// Loads the program without checking the configuration first.
+    pub(super) fn load<S: BufRead>(self, source: S) -> Result<Interpreter<I, O>, LoadError> {
+        let settings = self.settings;
+        let dialect = settings.dialect;
+        let mut playfield = match (settings.dimensions, dialect) {
+            (Some((width, height)), _) => Playfield::new(width, height),
+            (None, Dialect::Befunge93) => Playfield::default(),
+            (None, Dialect::Funge98) => Playfield::unbounded(),
+        };
+        let warnings = playfield.load(source, settings.load_mode)?;
+        let directions = match (settings.directions, settings.seed) {
+            (Some(directions), _) => directions,
+            (None, Some(seed)) => Box::new(RandomDirections::seeded(seed)),
+            (None, None) => Box::new(RandomDirections::default()),
+        };
+        Ok(Interpreter {
+            dialect,
+            playfield,
+            ip: Ip::new(0),
+            ips: VecDeque::new(),
+            next_id: 1,
+            directions,
+            executor: settings
+                .executor
+                .unwrap_or_else(|| Box::new(DisabledExecutor)),
+            fingerprints: settings.fingerprints,
+            division_by_zero: settings
+                .division_by_zero
+                .unwrap_or_else(|| dialect.division_by_zero()),
+            overflow: settings.overflow.unwrap_or_default(),
+            end_of_input: settings
+                .end_of_input
+                .unwrap_or_else(|| dialect.end_of_input()),
+            input_encoding: settings.input_encoding.unwrap_or_default(),
+            out_of_bounds: settings.out_of_bounds.unwrap_or_default(),
+            unknown_instruction: settings
+                .unknown_instruction
+                .unwrap_or_else(|| dialect.unknown_instruction()),
+            limits: settings.limits,
+            environment: settings.environment,
+            sandbox: settings.sandbox,
+            running: true,
+            exit_code: None,
+            stdin: Input::new(self.stdin),
+            stdout: self.stdout,
+            warnings,
+        })
+    }
+}
+
+impl Settings {
+    fn validate(&self) -> Result<(), ConfigError> {
+        if let Some((width, height)) = self.dimensions {
+            if width == 0 || height == 0 {
+                return Err(ConfigError::InvalidDimensions { width, height });
+            }
+        }
+        if self.seed.is_some() && self.directions.is_some() {
+            return Err(ConfigError::Conflict("seed", "direction_source"));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::InterpreterError;
+    use crate::fingerprint::{fingerprint_id, Semantic};
+    use crate::interpreter::{RunOutcome, StepOutcome};
+    use crate::pointer::Direction;
+    use crate::random::ScriptedDirections;
+
+    #[test]
+    fn default_settings() {
+        let interpreter = InterpreterBuilder::new().build("".as_bytes()).unwrap();
+        assert_eq!(80, interpreter.playfield().width());
+        assert_eq!(25, interpreter.playfield().height());
+        assert_eq!(DivisionByZero::Prompt, interpreter.division_by_zero);
+        assert_eq!(Overflow::Wrapping, interpreter.overflow);
+        assert_eq!(EndOfInput::PushMinusOne, interpreter.end_of_input);
+        assert_eq!(InputEncoding::Utf8, interpreter.input_encoding);
+        assert_eq!(OutOfBounds::Ignore, interpreter.out_of_bounds);
+        assert_eq!(UnknownInstruction::Error, interpreter.unknown_instruction);
+        assert_eq!(Limits::default(), interpreter.limits);
+    }
+
+    #[test]
+    fn dialect_defaults() {
+        let interpreter = InterpreterBuilder::new()
+            .dialect(Dialect::Funge98)
+            .build("".as_bytes())
+            .unwrap();
+        assert_eq!(DivisionByZero::PushZero, interpreter.division_by_zero);
+        assert_eq!(EndOfInput::Reflect, interpreter.end_of_input);
+        assert_eq!(UnknownInstruction::Reflect, interpreter.unknown_instruction);
+        assert!(interpreter.playfield().is_unbounded());
+    }
+
+    #[test]
+    fn funge98_dimensions() {
+        let interpreter = InterpreterBuilder::new()
+            .dialect(Dialect::Funge98)
+            .dimensions(100, 30)
+            .build("".as_bytes())
+            .unwrap();
+        assert!(!interpreter.playfield().is_unbounded());
+        assert_eq!(100, interpreter.playfield().width());
+    }
+
+    #[test]
+    fn funge98_put_get_outside_program() {
+        let mut interpreter = InterpreterBuilder::new()
+            .dialect(Dialect::Funge98)
+            .build("@".as_bytes())
+            .unwrap();
+        interpreter.ip.stack.push(7);
+        interpreter.ip.stack.push(-150);
+        interpreter.ip.stack.push(4000);
+        interpreter.command_execution('p').unwrap();
+        assert_eq!(
+            Some(((-150, 0), (0, 4000))),
+            interpreter.playfield().bounding_box()
+        );
+        interpreter.ip.stack.push(-150);
+        interpreter.ip.stack.push(4000);
+        interpreter.command_execution('g').unwrap();
+        interpreter.ip.stack.push(-1);
+        interpreter.ip.stack.push(-1);
+        interpreter.command_execution('g').unwrap();
+        assert_eq!(vec![7, ' ' as i32], interpreter.stack().values());
+    }
+
+    #[test]
+    fn funge98_lahey_wrap() {
+        let mut interpreter = InterpreterBuilder::new()
+            .dialect(Dialect::Funge98)
+            .build("<@1".as_bytes())
+            .unwrap();
+        interpreter.execute().unwrap();
+        assert_eq!((2, 0), interpreter.pointer().coordinates());
+        interpreter.execute().unwrap();
+        assert_eq!(StepOutcome::Ended, interpreter.execute().unwrap());
+        assert_eq!(vec![1], interpreter.stack().values());
+    }
+
+    #[derive(Debug)]
+    struct Empty;
+
+    impl Fingerprint for Empty {
+        fn id(&self) -> i32 {
+            fingerprint_id("EMPT")
+        }
+
+        fn semantics(&self) -> Vec<(char, Semantic)> {
+            vec![]
+        }
+    }
+
+    #[test]
+    fn fingerprint() {
+        let mut interpreter = InterpreterBuilder::new()
+            .dialect(Dialect::Funge98)
+            .fingerprint(Box::new(Empty))
+            .build("\"TPME\"4(@".as_bytes())
+            .unwrap();
+        assert_eq!(RunOutcome::Halted, interpreter.run().unwrap());
+        assert_eq!(
+            vec![fingerprint_id("EMPT"), 1],
+            interpreter.stack().values()
+        );
+        assert!(interpreter
+            .fingerprints
+            .get(fingerprint_id("NULL"))
+            .is_some());
+    }
+
+    #[test]
+    fn dialect_defaults_overridden() {
+        let interpreter = InterpreterBuilder::new()
+            .dialect(Dialect::Funge98)
+            .division_by_zero(DivisionByZero::Error)
+            .end_of_input(EndOfInput::PushMinusOne)
+            .build("".as_bytes())
+            .unwrap();
+        assert_eq!(DivisionByZero::Error, interpreter.division_by_zero);
+        assert_eq!(EndOfInput::PushMinusOne, interpreter.end_of_input);
+    }
+
+    #[test]
+    fn policies() {
+        let interpreter = InterpreterBuilder::new()
+            .overflow(Overflow::Error)
+            .input_encoding(InputEncoding::Bytes)
+            .out_of_bounds(OutOfBounds::Wrap)
+            .unknown_instruction(UnknownInstruction::Ignore)
+            .build("".as_bytes())
+            .unwrap();
+        assert_eq!(Overflow::Error, interpreter.overflow);
+        assert_eq!(InputEncoding::Bytes, interpreter.input_encoding);
+        assert_eq!(OutOfBounds::Wrap, interpreter.out_of_bounds);
+        assert_eq!(UnknownInstruction::Ignore, interpreter.unknown_instruction);
+    }
+
+    #[test]
+    fn dimensions() {
+        let source = format!("{}@", " ".repeat(99));
+        let interpreter = InterpreterBuilder::new()
+            .dimensions(100, 30)
+            .build(source.as_bytes())
+            .unwrap();
+        assert_eq!(100, interpreter.playfield().width());
+        assert_eq!(30, interpreter.playfield().height());
+        assert_eq!(Some('@' as i32), interpreter.playfield().get(99, 0));
+    }
+
+    #[test]
+    fn dimensions_invalid() {
+        match InterpreterBuilder::new()
+            .dimensions(0, 10)
+            .build("".as_bytes())
+        {
+            Err(BuildError::Config(ConfigError::InvalidDimensions { width, height })) => {
+                assert_eq!((0, 10), (width, height))
+            }
+            other => panic!("Unexpected result {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn dimensions_too_small() {
+        let result = InterpreterBuilder::new()
+            .dimensions(2, 1)
+            .build("abc".as_bytes());
+        match result {
+            Err(BuildError::Load(LoadError::OutsidePlayfield { line, column })) => {
+                assert_eq!((1, 3), (line, column))
+            }
+            other => panic!("Unexpected result {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn load_mode_truncate() {
+        let interpreter = InterpreterBuilder::new()
+            .dimensions(2, 1)
+            .load_mode(LoadMode::Truncate)
+            .build("abc".as_bytes())
+            .unwrap();
+        assert_eq!(1, interpreter.warnings().len());
+    }
+
+    #[test]
+    fn dimensions_wrap() {
+        let mut interpreter = InterpreterBuilder::new()
+            .dimensions(3, 1)
+            .build("1 #".as_bytes())
+            .unwrap();
+        for _ in 0..3 {
+            interpreter.execute().unwrap();
+        }
+        assert_eq!((1, 0), interpreter.pointer().coordinates());
+    }
+
+    #[test]
+    fn dimensions_put_get() {
+        let mut interpreter = InterpreterBuilder::new()
+            .dimensions(200, 50)
+            .out_of_bounds(OutOfBounds::Error)
+            .build("".as_bytes())
+            .unwrap();
+        interpreter.ip.stack.push(7);
+        interpreter.ip.stack.push(150);
+        interpreter.ip.stack.push(40);
+        interpreter.command_execution('p').unwrap();
+        assert_eq!(Some(7), interpreter.playfield().get(150, 40));
+        interpreter.ip.stack.push(200);
+        interpreter.ip.stack.push(0);
+        match interpreter.command_execution('g') {
+            Err(InterpreterError::OutOfBounds { x, y, .. }) => assert_eq!((200, 0), (x, y)),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn seed() {
+        let mut first = InterpreterBuilder::new()
+            .seed(3)
+            .build("".as_bytes())
+            .unwrap();
+        let mut second = InterpreterBuilder::new()
+            .seed(3)
+            .build("".as_bytes())
+            .unwrap();
+        for _ in 0..20 {
+            first.command_execution('?').unwrap();
+            second.command_execution('?').unwrap();
+            assert_eq!(first.ip.pointer.direction(), second.ip.pointer.direction());
+        }
+    }
+
+    #[test]
+    fn direction_source() {
+        let mut interpreter = InterpreterBuilder::new()
+            .direction_source(Box::new(ScriptedDirections::new(vec![Direction::Up])))
+            .build("".as_bytes())
+            .unwrap();
+        interpreter.command_execution('?').unwrap();
+        assert_eq!(Some(Direction::Up), interpreter.ip.pointer.direction());
+    }
+
+    #[test]
+    fn seed_conflicts_with_direction_source() {
+        let result = InterpreterBuilder::new()
+            .seed(3)
+            .direction_source(Box::new(ScriptedDirections::new(vec![Direction::Up])))
+            .build("".as_bytes());
+        match result {
+            Err(BuildError::Config(ConfigError::Conflict(first, second))) => {
+                assert_eq!(("seed", "direction_source"), (first, second))
+            }
+            other => panic!("Unexpected result {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn limits() {
+        let mut interpreter = InterpreterBuilder::new()
+            .limits(Limits {
+                max_steps: Some(10),
+                timeout: None,
+            })
+            .build(">v\n^<".as_bytes())
+            .unwrap();
+        assert_eq!(RunOutcome::OutOfFuel, interpreter.run().unwrap());
+    }
+
+    #[test]
+    fn io() {
+        let mut interpreter = InterpreterBuilder::new()
+            .stdin("5".as_bytes())
+            .stdout(vec![])
+            .build("&.@".as_bytes())
+            .unwrap();
+        interpreter.run().unwrap();
+        assert_eq!(b"5 ", interpreter.output().as_slice());
+    }
+}
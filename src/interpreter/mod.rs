@@ -1,246 +1,934 @@
+use crate::config::*;
+use crate::environment::Environment;
+use crate::error::{InterpreterError, LoadError, Warning};
+use crate::executor::Executor;
+use crate::fingerprint::{Fingerprint, FingerprintRegistry};
+use crate::input::Input;
+use crate::ip::{Ip, Repeat};
+use crate::playfield::Playfield;
 use crate::pointer::*;
+use crate::random::*;
+use crate::sandbox::Sandbox;
 use crate::stack::*;
-use rand::Rng;
-use std::{convert::TryInto, marker::PhantomData};
+use std::collections::VecDeque;
+use std::convert::{TryFrom, TryInto};
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::time::{Duration, Instant};
 
+mod builder;
+pub use builder::InterpreterBuilder;
+
+/// Result of executing a single instruction.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum StepOutcome {
+    /// The program can keep executing.
+    Running,
+    /// The program reached `@`.
+    Ended,
+}
+
+/// Bounds applied by [`Interpreter::run_with_limits`]. `None` means unbounded.
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub struct Limits {
+    pub max_steps: Option<u64>,
+    pub timeout: Option<Duration>,
+}
+
+/// Why [`Interpreter::run_with_limits`] stopped.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum RunOutcome {
+    /// The program reached `@`.
+    Halted,
+    /// The program ended with `q`, returning the given exit code.
+    Quit(i32),
+    /// The step budget was used up before the program ended.
+    OutOfFuel,
+    /// The timeout elapsed before the program ended.
+    TimedOut,
+}
+
+/// A Befunge-93 or Funge-98 interpreter, selected by [`Dialect`], reading
+/// from `stdin` and writing to `stdout`.
 #[derive(Debug)]
-pub struct Interpreter<S, I, O>
+pub struct Interpreter<I, O>
 where
-    S: std::io::BufRead,
-    I: std::io::BufRead,
-    O: std::io::Write,
+    I: BufRead,
+    O: Write,
 {
-    source_matrix: Vec<Vec<char>>,
-    pointer: Pointer,
-    stack: Stack,
+    dialect: Dialect,
+    playfield: Playfield,
+    ip: Ip,
+    ips: VecDeque<Ip>,
+    next_id: u64,
+    directions: Box<dyn DirectionSource>,
+    executor: Box<dyn Executor>,
+    fingerprints: FingerprintRegistry,
+    division_by_zero: DivisionByZero,
+    overflow: Overflow,
+    end_of_input: EndOfInput,
+    input_encoding: InputEncoding,
+    out_of_bounds: OutOfBounds,
+    unknown_instruction: UnknownInstruction,
+    limits: Limits,
+    environment: Environment,
+    sandbox: Option<Sandbox>,
     running: bool,
-    string_mode: bool,
-    stdin: I,
+    exit_code: Option<i32>,
+    stdin: Input<I>,
     stdout: O,
-    _marker: PhantomData<S>,
+    warnings: Vec<Warning>,
 }
 
-impl<S, I, O> Interpreter<S, I, O>
+impl<I, O> Interpreter<I, O>
 where
-    S: std::io::BufRead,
-    I: std::io::BufRead,
-    O: std::io::Write,
+    I: BufRead,
+    O: Write,
 {
-    pub fn new(source: S, stdin: I, stdout: O) -> Self {
-        let mut source_matrix = vec![vec!(' '; 80); 25];
-        for (x, line) in source.lines().enumerate() {
-            for (y, command) in line.unwrap().chars().enumerate() {
-                source_matrix[x][y] = command;
-            }
-        }
-        Interpreter {
-            source_matrix,
-            pointer: Pointer::default(),
-            stack: Stack::default(),
-            running: true,
-            string_mode: false,
-            stdin,
-            stdout,
-            _marker: PhantomData,
-        }
+    /// Loads the program line by line from `source`, failing if any
+    /// character falls outside the 80x25 playfield.
+    pub fn new<S: BufRead>(source: S, stdin: I, stdout: O) -> Result<Self, LoadError> {
+        Interpreter::load(source, stdin, stdout, LoadMode::Strict)
+    }
+
+    /// Loads the program line by line from `source`, handling characters
+    /// outside the 80x25 playfield according to `load_mode`.
+    pub fn load<S: BufRead>(
+        source: S,
+        stdin: I,
+        stdout: O,
+        load_mode: LoadMode,
+    ) -> Result<Self, LoadError> {
+        InterpreterBuilder::new()
+            .stdin(stdin)
+            .stdout(stdout)
+            .load_mode(load_mode)
+            .load(source)
+    }
+
+    /// Loads the program from a string.
+    pub fn from_source(source: &str, stdin: I, stdout: O) -> Result<Self, LoadError> {
+        Interpreter::new(source.as_bytes(), stdin, stdout)
+    }
+
+    /// Loads the program from any unbuffered reader, such as a `File`.
+    pub fn from_reader<R: Read>(source: R, stdin: I, stdout: O) -> Result<Self, LoadError> {
+        Interpreter::new(BufReader::new(source), stdin, stdout)
+    }
+
+    /// Replaces the source of directions used by `?`, for example with
+    /// [`RandomDirections::seeded`] to make runs reproducible.
+    pub fn set_direction_source(&mut self, directions: Box<dyn DirectionSource>) {
+        self.directions = directions;
+    }
+
+    /// Replaces the executor running the commands of the Funge-98 `=`
+    /// instruction.
+    pub fn set_executor(&mut self, executor: Box<dyn Executor>) {
+        self.executor = executor;
+    }
+
+    /// Makes a fingerprint available to the Funge-98 `(` instruction.
+    pub fn register_fingerprint(&mut self, fingerprint: Box<dyn Fingerprint>) {
+        self.fingerprints.register(fingerprint);
+    }
+
+    pub fn set_division_by_zero(&mut self, policy: DivisionByZero) {
+        self.division_by_zero = policy;
+    }
+
+    pub fn set_overflow(&mut self, overflow: Overflow) {
+        self.overflow = overflow;
+    }
+
+    pub fn set_end_of_input(&mut self, end_of_input: EndOfInput) {
+        self.end_of_input = end_of_input;
+    }
+
+    /// Selects whether `~` reads UTF-8 characters or single bytes.
+    pub fn set_input_encoding(&mut self, input_encoding: InputEncoding) {
+        self.input_encoding = input_encoding;
+    }
+
+    /// Selects what `p` and `g` do with coordinates outside the playfield.
+    pub fn set_out_of_bounds(&mut self, out_of_bounds: OutOfBounds) {
+        self.out_of_bounds = out_of_bounds;
+    }
+
+    pub fn set_unknown_instruction(&mut self, unknown_instruction: UnknownInstruction) {
+        self.unknown_instruction = unknown_instruction;
     }
 
     pub fn program_ended(&self) -> bool {
         !self.running
     }
 
-    pub fn execute(&mut self) {
-        if self.running {
-            let (x, y) = self.pointer.coordinates();
-            let command = self.source_matrix[x as usize][y as usize];
-            if self.string_mode {
-                self.string_mode_execution(command);
+    /// The exit code popped by `q`, once the program has ended through it.
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+
+    /// Executes the instruction under the current IP and moves it forward,
+    /// then passes control to the next IP. IPs take turns in a fixed order,
+    /// each child started by `t` running just before its parent. Every
+    /// repetition of a Funge-98 `k` takes a step of its own, during which the
+    /// IP keeps its turn.
+    pub fn execute(&mut self) -> Result<StepOutcome, InterpreterError> {
+        if !self.running {
+            return Err(InterpreterError::ProgramEnded);
+        }
+        if let Some(repeat) = self.ip.repeats.last_mut() {
+            repeat.remaining -= 1;
+            let command = repeat.command;
+            self.command_execution(command)?;
+        } else {
+            if self.dialect == Dialect::Funge98 && !self.ip.string_mode {
+                let (x, y) = self.find_instruction(self.ip.pointer.coordinates());
+                self.ip.pointer.set_coordinates(x, y);
+            }
+            let (x, y) = self.ip.pointer.coordinates();
+            let cell = self.playfield.get(x, y).unwrap_or(' ' as i32);
+            if self.ip.string_mode {
+                self.string_mode_execution(cell);
             } else {
-                self.command_execution(command);
+                let command =
+                    std::char::from_u32(cell as u32).unwrap_or(char::REPLACEMENT_CHARACTER);
+                self.command_execution(command)?;
             }
-            self.pointer.increase();
+        }
+        // The IP keeps its turn until its `k` instructions are done, each
+        // repetition taking one step of the run limits.
+        if self.running && !self.finish_repeats() {
+            return Ok(StepOutcome::Running);
+        }
+        self.ip.pointer.increase(&self.playfield);
+        if self.running {
+            if let Some(next) = self.ips.pop_front() {
+                let previous = std::mem::replace(&mut self.ip, next);
+                self.ips.push_back(previous);
+            }
+        } else if let Some(next) = self.ips.pop_front() {
+            self.ip = next;
+            self.running = true;
+        }
+        if self.running {
+            Ok(StepOutcome::Running)
         } else {
-            panic!("Cannot execute further on an ended program");
+            Ok(StepOutcome::Ended)
+        }
+    }
+
+    /// Executes instructions until the program reaches `@` or one of the
+    /// limits configured through [`InterpreterBuilder::limits`] is hit.
+    pub fn run(&mut self) -> Result<RunOutcome, InterpreterError> {
+        self.run_with_limits(self.limits)
+    }
+
+    /// Executes instructions until the program reaches `@` or `q`, or one of
+    /// the limits is hit. A program stopped by a limit can be resumed by calling
+    /// this method again.
+    pub fn run_with_limits(&mut self, limits: Limits) -> Result<RunOutcome, InterpreterError> {
+        // A timeout too large to represent never expires.
+        let deadline = limits
+            .timeout
+            .and_then(|timeout| Instant::now().checked_add(timeout));
+        let mut steps = 0;
+        loop {
+            if limits.max_steps.is_some_and(|max_steps| steps >= max_steps) {
+                return Ok(RunOutcome::OutOfFuel);
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return Ok(RunOutcome::TimedOut);
+            }
+            if self.execute()? == StepOutcome::Ended {
+                return Ok(match self.exit_code {
+                    Some(exit_code) => RunOutcome::Quit(exit_code),
+                    None => RunOutcome::Halted,
+                });
+            }
+            steps += 1;
         }
     }
 
-    fn string_mode_execution(&mut self, character: char) {
-        match character {
-            '"' => self.string_mode = false,
-            _ => self.stack.push_char(character),
+    /// The IP that executes the next instruction.
+    pub fn ip(&self) -> &Ip {
+        &self.ip
+    }
+
+    /// Every live IP, in the order in which they execute, starting from the
+    /// current one.
+    pub fn ips(&self) -> impl Iterator<Item = &Ip> {
+        std::iter::once(&self.ip).chain(self.ips.iter())
+    }
+
+    /// The top of the stack stack of the current IP, the only stack in
+    /// Befunge-93.
+    pub fn stack(&self) -> &Stack {
+        &self.ip.stack
+    }
+
+    pub fn stacks(&self) -> &StackStack {
+        &self.ip.stack
+    }
+
+    /// The offset added to the coordinates of `p` and `g`, set by `{` and `}`.
+    pub fn storage_offset(&self) -> (i32, i32) {
+        self.ip.storage_offset
+    }
+
+    pub fn pointer(&self) -> &Pointer {
+        &self.ip.pointer
+    }
+
+    /// The program grid. Cells hold the code point of the instruction or
+    /// the value last stored with `p`.
+    pub fn playfield(&self) -> &Playfield {
+        &self.playfield
+    }
+
+    /// Problems that did not prevent the program from running, such as
+    /// characters dropped while loading in [`LoadMode::Truncate`] or unknown
+    /// instructions skipped outside of [`UnknownInstruction::Error`].
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    pub fn output(&self) -> &O {
+        &self.stdout
+    }
+
+    pub fn into_output(self) -> O {
+        self.stdout
+    }
+
+    fn string_mode_execution(&mut self, cell: i32) {
+        if cell == '"' as i32 {
+            self.ip.string_mode = false;
+        } else {
+            self.ip.stack.push(cell);
         }
     }
 
-    fn command_execution(&mut self, command: char) {
+    fn command_execution(&mut self, command: char) -> Result<(), InterpreterError> {
         match command {
             '0' | '1' | '2' | '3' | '4' | '5' | '6' | '7' | '8' | '9' => self
+                .ip
                 .stack
                 .push(command.to_digit(10).unwrap().try_into().unwrap()),
-            '+' => {
-                let a = self.stack.pop();
-                let b = self.stack.pop();
-                self.stack.push(a + b);
-            }
-            '-' => {
-                let a = self.stack.pop();
-                let b = self.stack.pop();
-                self.stack.push(b - a);
-            }
-            '*' => {
-                let a = self.stack.pop();
-                let b = self.stack.pop();
-                self.stack.push(a * b);
-            }
-            '/' => {
-                let a = self.stack.pop();
-                let b = self.stack.pop();
-                self.stack.push(b / a);
-            }
-            '%' => {
-                let a = self.stack.pop();
-                let b = self.stack.pop();
-                self.stack.push(b % a);
+            'a' | 'b' | 'c' | 'd' | 'e' | 'f' if self.dialect == Dialect::Funge98 => self
+                .ip
+                .stack
+                .push(command.to_digit(16).unwrap().try_into().unwrap()),
+            '+' | '-' | '*' => {
+                let a = self.ip.stack.pop();
+                let b = self.ip.stack.pop();
+                let result = self.arithmetic(command, b, a)?;
+                self.ip.stack.push(result);
+            }
+            '/' | '%' => {
+                let a = self.ip.stack.pop();
+                let b = self.ip.stack.pop();
+                let result = if a == 0 {
+                    self.division_by_zero_result(command, b)?
+                } else {
+                    self.arithmetic(command, b, a)?
+                };
+                self.ip.stack.push(result);
             }
             '!' => {
-                let a = self.stack.pop();
+                let a = self.ip.stack.pop();
                 if a == 0 {
-                    self.stack.push(1);
+                    self.ip.stack.push(1);
                 } else {
-                    self.stack.push(0);
+                    self.ip.stack.push(0);
                 }
             }
             '`' => {
-                let a = self.stack.pop();
-                let b = self.stack.pop();
+                let a = self.ip.stack.pop();
+                let b = self.ip.stack.pop();
                 if b > a {
-                    self.stack.push(1);
+                    self.ip.stack.push(1);
                 } else {
-                    self.stack.push(0);
+                    self.ip.stack.push(0);
                 }
             }
             '>' => {
-                self.pointer.set_direction(Direction::Right);
+                self.ip.pointer.set_direction(Direction::Right);
             }
             '<' => {
-                self.pointer.set_direction(Direction::Left);
+                self.ip.pointer.set_direction(Direction::Left);
             }
             '^' => {
-                self.pointer.set_direction(Direction::Up);
+                self.ip.pointer.set_direction(Direction::Up);
             }
             'v' => {
-                self.pointer.set_direction(Direction::Down);
+                self.ip.pointer.set_direction(Direction::Down);
             }
             '?' => {
-                let new_direction = rand::thread_rng().gen_range(0, 4);
-                let pointer_direction;
-                match new_direction {
-                    0 => pointer_direction = Direction::Right,
-                    1 => pointer_direction = Direction::Left,
-                    2 => pointer_direction = Direction::Up,
-                    3 => pointer_direction = Direction::Down,
-                    _ => panic!("Generated number out of range"),
-                }
-                self.pointer.set_direction(pointer_direction);
+                let pointer_direction = self.directions.next_direction();
+                self.ip.pointer.set_direction(pointer_direction);
             }
             '_' => {
-                let condition = self.stack.pop();
-                let pointer_direction;
-                if condition == 0 {
-                    pointer_direction = Direction::Right;
+                let condition = self.ip.stack.pop();
+                let pointer_direction = if condition == 0 {
+                    Direction::Right
                 } else {
-                    pointer_direction = Direction::Left;
-                }
-                self.pointer.set_direction(pointer_direction)
+                    Direction::Left
+                };
+                self.ip.pointer.set_direction(pointer_direction)
             }
             '|' => {
-                let condition = self.stack.pop();
-                let pointer_direction;
-                if condition == 0 {
-                    pointer_direction = Direction::Down;
+                let condition = self.ip.stack.pop();
+                let pointer_direction = if condition == 0 {
+                    Direction::Down
                 } else {
-                    pointer_direction = Direction::Up;
-                }
-                self.pointer.set_direction(pointer_direction);
+                    Direction::Up
+                };
+                self.ip.pointer.set_direction(pointer_direction);
             }
-            '"' => self.string_mode = true,
+            '"' => self.ip.string_mode = true,
             ':' => {
-                let value = self.stack.pop();
-                self.stack.push(value);
-                self.stack.push(value);
+                let value = self.ip.stack.pop();
+                self.ip.stack.push(value);
+                self.ip.stack.push(value);
             }
             '\\' => {
-                let a = self.stack.pop();
-                let b = self.stack.pop();
-                self.stack.push(a);
-                self.stack.push(b);
+                let a = self.ip.stack.pop();
+                let b = self.ip.stack.pop();
+                self.ip.stack.push(a);
+                self.ip.stack.push(b);
             }
             '$' => {
-                let _value = self.stack.pop();
+                let _value = self.ip.stack.pop();
             }
             '.' => {
-                let value = self.stack.pop();
-                write!(&mut self.stdout, "{} ", value).unwrap();
-                self.stdout.flush().unwrap();
+                let value = self.ip.stack.pop();
+                write!(&mut self.stdout, "{} ", value)?;
+                self.stdout.flush()?;
             }
             ',' => {
-                let character = self.stack.pop_char();
-                write!(&mut self.stdout, "{}", character).unwrap();
-                self.stdout.flush().unwrap();
+                let character = self.ip.stack.pop_char()?;
+                write!(&mut self.stdout, "{}", character)?;
+                self.stdout.flush()?;
             }
             '#' => {
-                self.pointer.increase();
+                self.ip.pointer.increase(&self.playfield);
             }
             'p' => {
-                let y = self.stack.pop() as usize;
-                let x = self.stack.pop() as usize;
-                let v = self.stack.pop() as u8;
-                std::mem::replace(&mut self.source_matrix[x][y], v.into());
+                let (x, y) = self.pop_offset_vector();
+                let v = self.ip.stack.pop();
+                if let Some((x, y)) = self.resolve_coordinates(command, x, y)? {
+                    self.playfield.set(x, y, v);
+                }
             }
             'g' => {
-                let y = self.stack.pop() as usize;
-                let x = self.stack.pop() as usize;
-                if x < self.source_matrix.len() && y < self.source_matrix[x].len() {
-                    let read_char = self.source_matrix[x][y];
-                    let numeric_value = read_char as i32;
-                    self.stack.push(numeric_value);
+                let (x, y) = self.pop_offset_vector();
+                match self.resolve_coordinates(command, x, y)? {
+                    Some((x, y)) => self.ip.stack.push(self.playfield.get(x, y).unwrap_or(0)),
+                    None => self.ip.stack.push(0),
+                }
+            }
+            '&' => match self.stdin.read_number()? {
+                Some(read_number) => self.ip.stack.push(read_number),
+                None => self.end_of_input(),
+            },
+            '~' => match self.stdin.read_char(self.input_encoding)? {
+                Some(read_char) => self.ip.stack.push(read_char),
+                None => self.end_of_input(),
+            },
+            '@' => {
+                self.running = false;
+            }
+            'y' if self.dialect == Dialect::Funge98 => {
+                let count = self.ip.stack.pop();
+                let cells = self.system_info();
+                if count <= 0 {
+                    for cell in cells {
+                        self.ip.stack.push(cell);
+                    }
+                } else {
+                    let count = count as usize;
+                    let value = match count.checked_sub(cells.len()) {
+                        None | Some(0) => cells[cells.len() - count],
+                        Some(depth) => self.ip.stack.peek(depth - 1),
+                    };
+                    self.ip.stack.push(value);
+                }
+            }
+            'j' if self.dialect == Dialect::Funge98 => {
+                let count = self.ip.stack.pop();
+                if count < 0 {
+                    self.ip.pointer.reflect();
+                }
+                let (x, y) = self.playfield.advance_by(
+                    self.ip.pointer.coordinates(),
+                    self.ip.pointer.delta(),
+                    count.unsigned_abs(),
+                );
+                self.ip.pointer.set_coordinates(x, y);
+                if count < 0 {
+                    self.ip.pointer.reflect();
+                }
+            }
+            'k' if self.dialect == Dialect::Funge98 => {
+                let count = self.ip.stack.pop();
+                if count < 0 {
+                    self.ip.pointer.reflect();
+                    return Ok(());
+                }
+                let start = self.ip.pointer.coordinates();
+                let delta = self.ip.pointer.delta();
+                let (x, y) = self.find_instruction(self.playfield.advance(start, delta));
+                let cell = self.playfield.get(x, y).unwrap_or(' ' as i32);
+                let command =
+                    std::char::from_u32(cell as u32).unwrap_or(char::REPLACEMENT_CHARACTER);
+                if count == 0 {
+                    self.ip.pointer.set_coordinates(x, y);
                 } else {
-                    self.stack.push(0);
+                    self.ip.repeats.push(Repeat {
+                        command,
+                        remaining: count as u32,
+                        start,
+                        delta,
+                        target: (x, y),
+                    });
                 }
             }
-            '&' => {
-                let mut line = String::new();
-                self.stdin.read_line(&mut line).unwrap();
-                line.pop();
-                let read_char = line.parse::<i32>().unwrap();
-                self.stack.push(read_char.try_into().unwrap());
+            'x' if self.dialect == Dialect::Funge98 => {
+                let dy = self.ip.stack.pop();
+                let dx = self.ip.stack.pop();
+                self.ip.pointer.set_delta((dx, dy));
             }
-            '~' => {
-                let mut line = String::new();
-                self.stdin.read_line(&mut line).unwrap();
-                let read_char = line.chars().next().unwrap();
-                self.stack.push(read_char as i32);
+            '[' if self.dialect == Dialect::Funge98 => self.ip.pointer.turn_left(),
+            ']' if self.dialect == Dialect::Funge98 => self.ip.pointer.turn_right(),
+            'r' if self.dialect == Dialect::Funge98 => self.ip.pointer.reflect(),
+            'w' if self.dialect == Dialect::Funge98 => {
+                let b = self.ip.stack.pop();
+                let a = self.ip.stack.pop();
+                if a < b {
+                    self.ip.pointer.turn_left();
+                } else if a > b {
+                    self.ip.pointer.turn_right();
+                }
             }
-            '@' => {
+            '\'' if self.dialect == Dialect::Funge98 => {
+                self.ip.pointer.increase(&self.playfield);
+                let (x, y) = self.ip.pointer.coordinates();
+                let value = self.playfield.get(x, y).unwrap_or(' ' as i32);
+                self.ip.stack.push(value);
+            }
+            's' if self.dialect == Dialect::Funge98 => {
+                let value = self.ip.stack.pop();
+                self.ip.pointer.increase(&self.playfield);
+                let (x, y) = self.ip.pointer.coordinates();
+                self.playfield.set(x, y, value);
+            }
+            'n' if self.dialect == Dialect::Funge98 => self.ip.stack.clear(),
+            'z' if self.dialect == Dialect::Funge98 => (),
+            'q' if self.dialect == Dialect::Funge98 => {
+                self.exit_code = Some(self.ip.stack.pop());
+                self.ips.clear();
                 self.running = false;
             }
+            'i' if self.dialect == Dialect::Funge98 => {
+                let path = self.ip.stack.pop_string().ok();
+                let flags = self.ip.stack.pop();
+                let (x, y) = self.pop_vector();
+                let (offset_x, offset_y) = self.ip.storage_offset;
+                match path
+                    .zip(self.sandbox.as_ref())
+                    .map(|(path, sandbox)| sandbox.read(&path))
+                {
+                    Some(Ok(contents)) => {
+                        let origin = (x.wrapping_add(offset_x), y.wrapping_add(offset_y));
+                        let (width, height) =
+                            self.playfield.load_at(origin, &contents, flags & 1 != 0);
+                        for value in &[width, height, x, y] {
+                            self.ip.stack.push(*value);
+                        }
+                    }
+                    _ => self.ip.pointer.reflect(),
+                }
+            }
+            'o' if self.dialect == Dialect::Funge98 => {
+                let path = self.ip.stack.pop_string().ok();
+                let flags = self.ip.stack.pop();
+                let origin = self.pop_offset_vector();
+                let size = self.pop_vector();
+                let file = path
+                    .zip(self.sandbox.as_ref())
+                    .map(|(path, sandbox)| sandbox.create(&path));
+                let written = match file {
+                    Some(Ok(file)) => {
+                        let mut output = BufWriter::new(file);
+                        self.playfield
+                            .write_region(origin, size, flags & 1 != 0, &mut output)
+                            .and_then(|()| output.flush())
+                            .is_ok()
+                    }
+                    _ => false,
+                };
+                if !written {
+                    self.ip.pointer.reflect();
+                }
+            }
+            '=' if self.dialect == Dialect::Funge98 => {
+                let status = match self.ip.stack.pop_string() {
+                    Ok(command) => {
+                        self.stdout.flush()?;
+                        self.executor.execute(&command)
+                    }
+                    Err(_) => None,
+                };
+                match status {
+                    Some(status) => self.ip.stack.push(status),
+                    None => self.ip.pointer.reflect(),
+                }
+            }
+            '(' if self.dialect == Dialect::Funge98 => {
+                let id = self.pop_fingerprint_id();
+                match self.fingerprints.get(id) {
+                    Some(fingerprint) => {
+                        self.ip.semantics.load(fingerprint);
+                        self.ip.stack.push(id);
+                        self.ip.stack.push(1);
+                    }
+                    None => self.ip.pointer.reflect(),
+                }
+            }
+            ')' if self.dialect == Dialect::Funge98 => {
+                let id = self.pop_fingerprint_id();
+                match self.fingerprints.get(id) {
+                    Some(fingerprint) => self.ip.semantics.unload(fingerprint),
+                    None => self.ip.pointer.reflect(),
+                }
+            }
+            'A'..='Z' if self.dialect == Dialect::Funge98 => match self.ip.semantics.get(command) {
+                Some(semantic) => semantic(&mut self.ip, &mut self.playfield)?,
+                None => self.unknown_command(command)?,
+            },
+            't' if self.dialect == Dialect::Funge98 => {
+                let mut child = self.ip.split(self.next_id);
+                self.next_id += 1;
+                child.pointer.increase(&self.playfield);
+                self.ips.push_back(child);
+            }
+            '{' if self.dialect == Dialect::Funge98 => {
+                self.ip.stack.begin_block(self.ip.storage_offset);
+                let (x, y) = self.ip.pointer.coordinates();
+                let (dx, dy) = self.ip.pointer.delta();
+                self.ip.storage_offset = (x.wrapping_add(dx), y.wrapping_add(dy));
+            }
+            '}' if self.dialect == Dialect::Funge98 => match self.ip.stack.end_block() {
+                Some(storage_offset) => self.ip.storage_offset = storage_offset,
+                None => self.ip.pointer.reflect(),
+            },
+            'u' if self.dialect == Dialect::Funge98 => {
+                if !self.ip.stack.under() {
+                    self.ip.pointer.reflect();
+                }
+            }
             ' ' => (),
-            _ => panic!("Unknown command '{}'", command),
+            _ => self.unknown_command(command)?,
+        }
+        Ok(())
+    }
+
+    /// Drops the `k` instructions of the current IP that are done, moving
+    /// the IP onto the instruction they repeated unless it moved meanwhile.
+    /// Returns whether none is left repeating.
+    fn finish_repeats(&mut self) -> bool {
+        while let Some(repeat) = self.ip.repeats.last() {
+            if repeat.remaining > 0 {
+                return false;
+            }
+            let pointer = &mut self.ip.pointer;
+            if pointer.coordinates() == repeat.start && pointer.delta() == repeat.delta {
+                pointer.set_coordinates(repeat.target.0, repeat.target.1);
+            }
+            self.ip.repeats.pop();
+        }
+        true
+    }
+
+    /// Handles a command the dialect does not define, according to the
+    /// unknown instruction policy.
+    fn unknown_command(&mut self, command: char) -> Result<(), InterpreterError> {
+        let (x, y) = self.ip.pointer.coordinates();
+        if self.unknown_instruction == UnknownInstruction::Error {
+            return Err(InterpreterError::UnknownInstruction { command, x, y });
+        }
+        let warning = Warning::UnknownInstruction { command, x, y };
+        if !self.warnings.contains(&warning) {
+            self.warnings.push(warning);
+        }
+        if self.unknown_instruction == UnknownInstruction::Reflect {
+            self.ip.pointer.reflect();
+        }
+        Ok(())
+    }
+
+    /// The cells pushed by `y`, from the bottom of the stack to the top: the
+    /// environment variables, the command-line arguments, the stack sizes,
+    /// the date and time, the playfield bounds, the IP vectors and finally
+    /// the properties of the interpreter.
+    fn system_info(&self) -> Vec<i32> {
+        let mut cells = vec![0];
+        let variables: Vec<String> = self
+            .environment
+            .variables()
+            .into_iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        push_strings(&mut cells, &variables);
+        cells.extend(&[0, 0]);
+        push_strings(&mut cells, &self.environment.arguments);
+        let stacks = self.ip.stack.stacks();
+        cells.extend(
+            stacks
+                .iter()
+                .map(|stack| stack.len().try_into().unwrap_or(i32::MAX)),
+        );
+        cells.push(stacks.len() as i32);
+        let (date, time) = self.environment.date_and_time();
+        cells.extend(&[time, date]);
+        let ((min_x, min_y), (max_x, max_y)) = if self.playfield.is_unbounded() {
+            self.playfield.bounding_box().unwrap_or(((0, 0), (0, 0)))
+        } else {
+            let (width, height) = (self.playfield.width(), self.playfield.height());
+            ((0, 0), (width as i32 - 1, height as i32 - 1))
+        };
+        cells.extend(&[
+            max_x.saturating_sub(min_x),
+            max_y.saturating_sub(min_y),
+            min_x,
+            min_y,
+        ]);
+        let (offset_x, offset_y) = self.ip.storage_offset;
+        let (dx, dy) = self.ip.pointer.delta();
+        let (x, y) = self.ip.pointer.coordinates();
+        cells.extend(&[offset_x, offset_y, dx, dy, x, y]);
+        cells.extend(&[
+            0,
+            self.ip.id as i32,
+            2,
+            std::path::MAIN_SEPARATOR as i32,
+            self.executor.paradigm(),
+            version(),
+            HANDPRINT,
+            4,
+            self.flags(),
+        ]);
+        cells
+    }
+
+    /// The first cell holding an instruction at or after `position` along
+    /// the delta of the current IP. Spaces and `;` comments are skipped, as
+    /// they take no time in Funge-98. Paths that hold no instruction give
+    /// back `position`.
+    fn find_instruction(&self, position: (i32, i32)) -> (i32, i32) {
+        let delta = self.ip.pointer.delta();
+        let mut current = position;
+        let mut comment = false;
+        for _ in 0..self.path_limit() {
+            match self.playfield.get(current.0, current.1) {
+                Some(cell) if cell == ';' as i32 => comment = !comment,
+                Some(cell) if cell != ' ' as i32 && !comment => return current,
+                _ => (),
+            }
+            current = match self.playfield.skip_spaces(current, delta) {
+                Some(next) => next,
+                None => return position,
+            };
+        }
+        position
+    }
+
+    /// The number of steps after which a search along the delta of the
+    /// current IP is known to have visited every cell it will ever reach.
+    /// Unbounded playfields skip straight between non-space cells, so every
+    /// one of them is reached within two passes.
+    fn path_limit(&self) -> usize {
+        if self.playfield.is_unbounded() {
+            self.playfield
+                .occupied()
+                .saturating_add(1)
+                .saturating_mul(2)
+        } else {
+            (self.playfield.width() + 1).saturating_mul(self.playfield.height() + 1)
+        }
+    }
+
+    /// The flags cell of `y`: `t` is always implemented, `i` and `o` only
+    /// when file access is enabled and `=` only with an executor that can
+    /// run commands.
+    fn flags(&self) -> i32 {
+        let mut flags = 0x01;
+        if self.sandbox.is_some() {
+            flags |= 0x06;
+        }
+        if self.executor.paradigm() != 0 {
+            flags |= 0x08;
+        }
+        flags
+    }
+
+    /// Pops the count and the cells of a fingerprint name pushed for `(`
+    /// and `)`, reading them as base-256 digits.
+    fn pop_fingerprint_id(&mut self) -> i32 {
+        let count = usize::try_from(self.ip.stack.pop()).unwrap_or(0);
+        // Digits popped before the last four are shifted out of the id.
+        self.ip.stack.discard(count.saturating_sub(4));
+        (0..count.min(4)).fold(0, |id: i32, _| {
+            id.wrapping_mul(256).wrapping_add(self.ip.stack.pop())
+        })
+    }
+
+    fn pop_vector(&mut self) -> (i32, i32) {
+        let y = self.ip.stack.pop();
+        let x = self.ip.stack.pop();
+        (x, y)
+    }
+
+    /// Pops a vector, such as the coordinates of `p` and `g`, and adds the
+    /// storage offset.
+    fn pop_offset_vector(&mut self) -> (i32, i32) {
+        let (x, y) = self.pop_vector();
+        (
+            x.wrapping_add(self.ip.storage_offset.0),
+            y.wrapping_add(self.ip.storage_offset.1),
+        )
+    }
+
+    /// Resolves the coordinates popped by `p` and `g` into a cell of the
+    /// playfield. Returns `None` when the access is ignored.
+    fn resolve_coordinates(
+        &self,
+        command: char,
+        x: i32,
+        y: i32,
+    ) -> Result<Option<(i32, i32)>, InterpreterError> {
+        if self.playfield.in_bounds(x, y) {
+            return Ok(Some((x, y)));
+        }
+        match self.out_of_bounds {
+            OutOfBounds::Ignore => Ok(None),
+            OutOfBounds::Wrap => Ok(Some(self.playfield.wrap(x, y))),
+            OutOfBounds::Error => Err(InterpreterError::OutOfBounds { command, x, y }),
+        }
+    }
+
+    fn end_of_input(&mut self) {
+        match self.end_of_input {
+            EndOfInput::PushMinusOne => self.ip.stack.push(-1),
+            EndOfInput::Reflect => self.ip.pointer.reflect(),
+        }
+    }
+
+    /// Applies an arithmetic instruction to `b` and `a` (`a` being the top of
+    /// the stack), resolving overflows according to the overflow mode.
+    fn arithmetic(&self, command: char, b: i32, a: i32) -> Result<i32, InterpreterError> {
+        let checked = match command {
+            '+' => b.checked_add(a),
+            '-' => b.checked_sub(a),
+            '*' => b.checked_mul(a),
+            '/' => b.checked_div(a),
+            _ => b.checked_rem(a),
+        };
+        match (checked, self.overflow) {
+            (Some(result), _) => Ok(result),
+            (None, Overflow::Wrapping) => Ok(match command {
+                '+' => b.wrapping_add(a),
+                '-' => b.wrapping_sub(a),
+                '*' => b.wrapping_mul(a),
+                '/' => b.wrapping_div(a),
+                _ => b.wrapping_rem(a),
+            }),
+            (None, Overflow::Saturating) => Ok(match command {
+                '+' => b.saturating_add(a),
+                '-' => b.saturating_sub(a),
+                '*' => b.saturating_mul(a),
+                '/' => b.saturating_div(a),
+                _ => 0,
+            }),
+            (None, Overflow::Error) => {
+                let (x, y) = self.ip.pointer.coordinates();
+                Err(InterpreterError::Overflow { command, x, y })
+            }
+        }
+    }
+
+    fn division_by_zero_result(
+        &mut self,
+        command: char,
+        dividend: i32,
+    ) -> Result<i32, InterpreterError> {
+        match self.division_by_zero {
+            DivisionByZero::Prompt => {
+                write!(
+                    &mut self.stdout,
+                    "What do you want {}{}0 to be? ",
+                    dividend, command
+                )?;
+                self.stdout.flush()?;
+                self.stdin
+                    .read_number()?
+                    .ok_or(InterpreterError::EndOfInput { command, dividend })
+            }
+            DivisionByZero::PushZero => Ok(0),
+            DivisionByZero::Error => {
+                let (x, y) = self.ip.pointer.coordinates();
+                Err(InterpreterError::DivisionByZero { command, x, y })
+            }
         }
     }
 }
 
+/// Identifies bfng in the cells of `y`: "BFNG" in ASCII.
+const HANDPRINT: i32 = 0x4246_4E47;
+
+/// The version of bfng as reported by `y`, `major * 10000 + minor * 100 + patch`.
+fn version() -> i32 {
+    let part = |value: &str| value.parse::<i32>().unwrap_or(0);
+    part(env!("CARGO_PKG_VERSION_MAJOR")) * 10000
+        + part(env!("CARGO_PKG_VERSION_MINOR")) * 100
+        + part(env!("CARGO_PKG_VERSION_PATCH"))
+}
+
+/// Pushes each string null-terminated and reversed, so that the first
+/// string reads from the top of the stack.
+fn push_strings(cells: &mut Vec<i32>, strings: &[String]) {
+    for string in strings.iter().rev() {
+        cells.push(0);
+        cells.extend(string.chars().rev().map(|character| character as i32));
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use crate::executor::ScriptedExecutor;
+    use crate::fingerprint::fingerprint_id;
+    use crate::fingerprint::test_support::Push;
+    use crate::sandbox::test_support::TempDir;
+    use std::io;
     use std::str;
+    use std::time::UNIX_EPOCH;
 
     fn new_interpreter<'a>(
         source: &'a mut String,
         stdin: &'a mut String,
-        stdout: &'a mut Vec<u8>,
-    ) -> Interpreter<&'a [u8], &'a [u8], Vec<u8>> {
-        Interpreter::new(source.as_bytes(), stdin.as_bytes(), stdout.to_vec())
+        stdout: &'a mut [u8],
+    ) -> Interpreter<&'a [u8], Vec<u8>> {
+        Interpreter::new(source.as_bytes(), stdin.as_bytes(), stdout.to_vec()).unwrap()
+    }
+
+    fn new_funge98_interpreter(source: &str) -> Interpreter<io::Empty, Vec<u8>> {
+        InterpreterBuilder::new()
+            .dialect(Dialect::Funge98)
+            .stdout(vec![])
+            .build(source.as_bytes())
+            .unwrap()
     }
 
     #[test]
@@ -250,12 +938,9 @@ mod tests {
         let mut stdout = vec![];
         let interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
         assert!(interpreter.running);
-        assert!(!interpreter.string_mode);
-        let source_matrix = interpreter.source_matrix;
-        assert_eq!(25, source_matrix.len());
-        for line in source_matrix {
-            assert_eq!(80, line.len());
-        }
+        assert!(!interpreter.ip.string_mode);
+        assert_eq!(25, interpreter.playfield.height());
+        assert_eq!(80, interpreter.playfield.width());
     }
 
     #[test]
@@ -274,21 +959,180 @@ mod tests {
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
         assert!(!interpreter.program_ended(), "Program shouldn't be ended");
-        interpreter.execute();
+        interpreter.execute().unwrap();
         assert!(interpreter.program_ended(), "Program should be ended");
     }
 
     #[test]
-    #[should_panic]
-    fn execute_panic() {
+    fn execute_after_end() {
         let mut source = String::from("@");
         let mut stdin = String::from("");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
         assert!(!interpreter.program_ended(), "Program shouldn't be ended");
-        interpreter.execute();
+        assert_eq!(StepOutcome::Ended, interpreter.execute().unwrap());
         assert!(interpreter.program_ended(), "Program should be ended");
-        interpreter.execute();
+        match interpreter.execute() {
+            Err(InterpreterError::ProgramEnded) => (),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn execute_running() {
+        let mut source = String::from(" @");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        assert_eq!(StepOutcome::Running, interpreter.execute().unwrap());
+        assert_eq!(StepOutcome::Ended, interpreter.execute().unwrap());
+    }
+
+    #[test]
+    fn from_source() {
+        let interpreter = Interpreter::from_source("12", "".as_bytes(), vec![]).unwrap();
+        assert_eq!('1' as i32, interpreter.playfield().get(0, 0).unwrap());
+        assert_eq!('2' as i32, interpreter.playfield().get(1, 0).unwrap());
+    }
+
+    #[test]
+    fn from_reader() {
+        let interpreter =
+            Interpreter::from_reader("1\n2".as_bytes(), "".as_bytes(), vec![]).unwrap();
+        assert_eq!('1' as i32, interpreter.playfield().get(0, 0).unwrap());
+        assert_eq!('2' as i32, interpreter.playfield().get(0, 1).unwrap());
+    }
+
+    #[test]
+    fn new_line_too_long() {
+        let source = format!("{}@", " ".repeat(80));
+        match Interpreter::from_source(&source, "".as_bytes(), vec![]) {
+            Err(LoadError::OutsidePlayfield { line, column }) => {
+                assert_eq!((1, 81), (line, column))
+            }
+            other => panic!("Unexpected result {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn new_too_many_lines() {
+        let source = format!("{} @", "\n".repeat(25));
+        match Interpreter::from_source(&source, "".as_bytes(), vec![]) {
+            Err(LoadError::OutsidePlayfield { line, column }) => {
+                assert_eq!((26, 2), (line, column))
+            }
+            other => panic!("Unexpected result {:?}", other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn new_trailing_whitespace() {
+        let source = format!("{}\n{}", " ".repeat(100), "\n".repeat(30));
+        let interpreter = Interpreter::from_source(&source, "".as_bytes(), vec![]).unwrap();
+        assert!(interpreter.warnings().is_empty());
+    }
+
+    #[test]
+    fn load_truncate() {
+        let source = format!("{}ab\n@", "1".repeat(80));
+        let interpreter =
+            Interpreter::load(source.as_bytes(), "".as_bytes(), vec![], LoadMode::Truncate)
+                .unwrap();
+        assert_eq!(
+            &[Warning::Truncated {
+                line: 1,
+                column: 81
+            }],
+            interpreter.warnings()
+        );
+        assert_eq!('1' as i32, interpreter.playfield().get(79, 0).unwrap());
+        assert_eq!('@' as i32, interpreter.playfield().get(0, 1).unwrap());
+    }
+
+    #[test]
+    fn run() {
+        let mut interpreter = Interpreter::from_source("\"iH\",,@", "".as_bytes(), vec![]).unwrap();
+        interpreter.run().unwrap();
+        assert!(interpreter.program_ended());
+        assert_eq!("Hi", str::from_utf8(&interpreter.into_output()).unwrap());
+    }
+
+    #[test]
+    fn run_with_limits_halted() {
+        let mut interpreter = Interpreter::from_source("12@", "".as_bytes(), vec![]).unwrap();
+        let limits = Limits {
+            max_steps: Some(3),
+            timeout: None,
+        };
+        assert_eq!(
+            RunOutcome::Halted,
+            interpreter.run_with_limits(limits).unwrap()
+        );
+    }
+
+    #[test]
+    fn run_with_limits_out_of_fuel() {
+        let mut interpreter = Interpreter::from_source(">v\n^<", "".as_bytes(), vec![]).unwrap();
+        let limits = Limits {
+            max_steps: Some(100),
+            timeout: None,
+        };
+        assert_eq!(
+            RunOutcome::OutOfFuel,
+            interpreter.run_with_limits(limits).unwrap()
+        );
+        assert!(!interpreter.program_ended());
+    }
+
+    #[test]
+    fn run_with_limits_resume() {
+        let mut interpreter = Interpreter::from_source("12@", "".as_bytes(), vec![]).unwrap();
+        let limits = Limits {
+            max_steps: Some(2),
+            timeout: None,
+        };
+        assert_eq!(
+            RunOutcome::OutOfFuel,
+            interpreter.run_with_limits(limits).unwrap()
+        );
+        assert_eq!(
+            RunOutcome::Halted,
+            interpreter.run_with_limits(limits).unwrap()
+        );
+    }
+
+    #[test]
+    fn run_with_limits_timed_out() {
+        let mut interpreter = Interpreter::from_source(">v\n^<", "".as_bytes(), vec![]).unwrap();
+        let limits = Limits {
+            max_steps: None,
+            timeout: Some(Duration::from_millis(10)),
+        };
+        assert_eq!(
+            RunOutcome::TimedOut,
+            interpreter.run_with_limits(limits).unwrap()
+        );
+    }
+
+    #[test]
+    fn run_with_limits_huge_timeout() {
+        let mut interpreter = Interpreter::from_source(">v\n^<", "".as_bytes(), vec![]).unwrap();
+        let limits = Limits {
+            max_steps: Some(10),
+            timeout: Some(Duration::MAX),
+        };
+        assert_eq!(
+            RunOutcome::OutOfFuel,
+            interpreter.run_with_limits(limits).unwrap()
+        );
+    }
+
+    #[test]
+    fn run_inspect_stack() {
+        let mut interpreter = Interpreter::from_source("12@", "".as_bytes(), vec![]).unwrap();
+        interpreter.run().unwrap();
+        assert_eq!(vec![1, 2], interpreter.stack().values());
+        assert_eq!((3, 0), interpreter.pointer().coordinates());
     }
 
     #[test]
@@ -297,8 +1141,8 @@ mod tests {
         let mut stdin = String::from("");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.string_mode_execution('A');
-        assert_eq!(65, interpreter.stack.pop());
+        interpreter.string_mode_execution('A' as i32);
+        assert_eq!(65, interpreter.ip.stack.pop());
     }
 
     #[test]
@@ -307,10 +1151,10 @@ mod tests {
         let mut stdin = String::from("");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.string_mode = true;
-        interpreter.string_mode_execution('"');
-        assert_eq!(0, interpreter.stack.pop());
-        assert!(!interpreter.string_mode);
+        interpreter.ip.string_mode = true;
+        interpreter.string_mode_execution('"' as i32);
+        assert_eq!(0, interpreter.ip.stack.pop());
+        assert!(!interpreter.ip.string_mode);
     }
 
     #[test]
@@ -321,8 +1165,11 @@ mod tests {
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
         for digit in digits {
-            interpreter.command_execution(digit);
-            assert_eq!(digit.to_digit(10).unwrap(), interpreter.stack.pop() as u32);
+            interpreter.command_execution(digit).unwrap();
+            assert_eq!(
+                digit.to_digit(10).unwrap(),
+                interpreter.ip.stack.pop() as u32
+            );
         }
     }
 
@@ -332,262 +1179,497 @@ mod tests {
         let mut stdin = String::from("");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.stack.push(1);
-        interpreter.stack.push(2);
-        interpreter.command_execution('+');
-        assert_eq!(3, interpreter.stack.pop());
+        interpreter.ip.stack.push(1);
+        interpreter.ip.stack.push(2);
+        interpreter.command_execution('+').unwrap();
+        assert_eq!(3, interpreter.ip.stack.pop());
     }
 
     #[test]
-    fn command_execution_subtract() {
+    fn command_execution_add_overflow_wrapping() {
         let mut source = String::from("@");
         let mut stdin = String::from("");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.stack.push(1);
-        interpreter.stack.push(2);
-        interpreter.command_execution('-');
-        assert_eq!(-1, interpreter.stack.pop());
+        interpreter.ip.stack.push(i32::MAX);
+        interpreter.ip.stack.push(1);
+        interpreter.command_execution('+').unwrap();
+        assert_eq!(i32::MIN, interpreter.ip.stack.pop());
     }
 
     #[test]
-    fn command_execution_multiply() {
+    fn command_execution_add_overflow_saturating() {
         let mut source = String::from("@");
         let mut stdin = String::from("");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.stack.push(3);
-        interpreter.stack.push(2);
-        interpreter.command_execution('*');
-        assert_eq!(6, interpreter.stack.pop());
+        interpreter.set_overflow(Overflow::Saturating);
+        interpreter.ip.stack.push(i32::MAX);
+        interpreter.ip.stack.push(1);
+        interpreter.command_execution('+').unwrap();
+        assert_eq!(i32::MAX, interpreter.ip.stack.pop());
     }
 
     #[test]
-    fn command_execution_divide() {
+    fn command_execution_add_overflow_error() {
         let mut source = String::from("@");
         let mut stdin = String::from("");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.stack.push(10);
-        interpreter.stack.push(2);
-        interpreter.command_execution('/');
-        assert_eq!(5, interpreter.stack.pop());
+        interpreter.set_overflow(Overflow::Error);
+        interpreter.ip.stack.push(i32::MAX);
+        interpreter.ip.stack.push(1);
+        match interpreter.command_execution('+') {
+            Err(InterpreterError::Overflow { command, x, y }) => {
+                assert_eq!('+', command);
+                assert_eq!((0, 0), (x, y));
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
     }
 
     #[test]
-    fn command_execution_modulus() {
+    fn command_execution_subtract() {
         let mut source = String::from("@");
         let mut stdin = String::from("");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.stack.push(5);
-        interpreter.stack.push(2);
-        interpreter.command_execution('%');
-        assert_eq!(1, interpreter.stack.pop());
+        interpreter.ip.stack.push(1);
+        interpreter.ip.stack.push(2);
+        interpreter.command_execution('-').unwrap();
+        assert_eq!(-1, interpreter.ip.stack.pop());
     }
 
     #[test]
-    fn command_execution_not_true() {
+    fn command_execution_multiply() {
         let mut source = String::from("@");
         let mut stdin = String::from("");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.stack.push(0);
-        interpreter.command_execution('!');
-        assert_eq!(1, interpreter.stack.pop());
+        interpreter.ip.stack.push(3);
+        interpreter.ip.stack.push(2);
+        interpreter.command_execution('*').unwrap();
+        assert_eq!(6, interpreter.ip.stack.pop());
     }
 
     #[test]
-    fn command_execution_not_false() {
+    fn command_execution_subtract_overflow() {
         let mut source = String::from("@");
         let mut stdin = String::from("");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.stack.push(78);
-        interpreter.command_execution('!');
-        assert_eq!(0, interpreter.stack.pop());
+        interpreter.ip.stack.push(i32::MIN);
+        interpreter.ip.stack.push(1);
+        interpreter.command_execution('-').unwrap();
+        assert_eq!(i32::MAX, interpreter.ip.stack.pop());
+        interpreter.set_overflow(Overflow::Saturating);
+        interpreter.ip.stack.push(i32::MIN);
+        interpreter.ip.stack.push(1);
+        interpreter.command_execution('-').unwrap();
+        assert_eq!(i32::MIN, interpreter.ip.stack.pop());
     }
 
     #[test]
-    fn command_execution_greater_than_greater() {
+    fn command_execution_multiply_overflow() {
         let mut source = String::from("@");
         let mut stdin = String::from("");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.stack.push(99);
-        interpreter.stack.push(0);
-        interpreter.command_execution('`');
-        assert_eq!(1, interpreter.stack.pop());
+        interpreter.ip.stack.push(i32::MAX);
+        interpreter.ip.stack.push(2);
+        interpreter.command_execution('*').unwrap();
+        assert_eq!(-2, interpreter.ip.stack.pop());
+        interpreter.set_overflow(Overflow::Saturating);
+        interpreter.ip.stack.push(i32::MAX);
+        interpreter.ip.stack.push(2);
+        interpreter.command_execution('*').unwrap();
+        assert_eq!(i32::MAX, interpreter.ip.stack.pop());
     }
 
     #[test]
-    fn command_execution_greater_than_lesser() {
+    fn command_execution_divide_overflow() {
         let mut source = String::from("@");
         let mut stdin = String::from("");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.stack.push(0);
-        interpreter.stack.push(99);
-        interpreter.command_execution('`');
-        assert_eq!(0, interpreter.stack.pop());
+        interpreter.ip.stack.push(i32::MIN);
+        interpreter.ip.stack.push(-1);
+        interpreter.command_execution('/').unwrap();
+        assert_eq!(i32::MIN, interpreter.ip.stack.pop());
+        interpreter.set_overflow(Overflow::Saturating);
+        interpreter.ip.stack.push(i32::MIN);
+        interpreter.ip.stack.push(-1);
+        interpreter.command_execution('/').unwrap();
+        assert_eq!(i32::MAX, interpreter.ip.stack.pop());
+        interpreter.set_overflow(Overflow::Error);
+        interpreter.ip.stack.push(i32::MIN);
+        interpreter.ip.stack.push(-1);
+        assert!(interpreter.command_execution('/').is_err());
     }
 
     #[test]
-    fn pointer_right() {
+    fn command_execution_modulus_overflow() {
         let mut source = String::from("@");
         let mut stdin = String::from("");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.pointer.set_direction(Direction::Down);
-        interpreter.command_execution('>');
-        assert_eq!(Direction::Right, interpreter.pointer.direction());
+        interpreter.ip.stack.push(i32::MIN);
+        interpreter.ip.stack.push(-1);
+        interpreter.command_execution('%').unwrap();
+        assert_eq!(0, interpreter.ip.stack.pop());
+        interpreter.set_overflow(Overflow::Saturating);
+        interpreter.ip.stack.push(i32::MIN);
+        interpreter.ip.stack.push(-1);
+        interpreter.command_execution('%').unwrap();
+        assert_eq!(0, interpreter.ip.stack.pop());
     }
 
     #[test]
-    fn pointer_left() {
+    fn command_execution_divide() {
         let mut source = String::from("@");
         let mut stdin = String::from("");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.command_execution('<');
-        assert_eq!(Direction::Left, interpreter.pointer.direction());
+        interpreter.ip.stack.push(10);
+        interpreter.ip.stack.push(2);
+        interpreter.command_execution('/').unwrap();
+        assert_eq!(5, interpreter.ip.stack.pop());
     }
 
     #[test]
-    fn pointer_up() {
+    fn command_execution_divide_by_zero_prompt() {
         let mut source = String::from("@");
-        let mut stdin = String::from("");
+        let mut stdin = String::from("42\n");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.pointer.set_direction(Direction::Down);
-        interpreter.command_execution('^');
-        assert_eq!(Direction::Up, interpreter.pointer.direction());
+        interpreter.ip.stack.push(10);
+        interpreter.ip.stack.push(0);
+        interpreter.command_execution('/').unwrap();
+        assert_eq!(42, interpreter.ip.stack.pop());
+        assert_eq!(
+            "What do you want 10/0 to be? ",
+            str::from_utf8(&interpreter.stdout).unwrap()
+        );
     }
 
     #[test]
-    fn pointer_down() {
+    fn command_execution_divide_by_zero_prompt_end_of_input() {
         let mut source = String::from("@");
         let mut stdin = String::from("");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.pointer.set_direction(Direction::Down);
-        interpreter.command_execution('v');
-        assert_eq!(Direction::Down, interpreter.pointer.direction());
+        interpreter.ip.stack.push(10);
+        interpreter.ip.stack.push(0);
+        match interpreter.command_execution('/') {
+            Err(InterpreterError::EndOfInput { command, dividend }) => {
+                assert_eq!(('/', 10), (command, dividend));
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
     }
 
     #[test]
-    fn pointer_random() {
+    fn command_execution_divide_by_zero_push_zero() {
         let mut source = String::from("@");
         let mut stdin = String::from("");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.pointer.set_direction(Direction::Down);
-        interpreter.command_execution('?');
-        /*
-            Since there's a 1/4 chance that the random direction will be Right, I cannot test the method with assert_ne!(Direction::Right, interpreter.pointer.direction()).
-            A way around might be to check if the function set_direction was called interpreter.pointer but I don't know how to do that and it's two o'clock in the morning
-        */
+        interpreter.set_division_by_zero(DivisionByZero::PushZero);
+        interpreter.ip.stack.push(10);
+        interpreter.ip.stack.push(0);
+        interpreter.command_execution('/').unwrap();
+        assert_eq!(0, interpreter.ip.stack.pop());
     }
 
     #[test]
-    fn command_execution_horizontal_if_zero() {
+    fn command_execution_divide_by_zero_error() {
         let mut source = String::from("@");
         let mut stdin = String::from("");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.stack.push(0);
-        interpreter.command_execution('_');
-        assert_eq!(Direction::Right, interpreter.pointer.direction());
+        interpreter.set_division_by_zero(DivisionByZero::Error);
+        interpreter.ip.stack.push(10);
+        interpreter.ip.stack.push(0);
+        match interpreter.command_execution('/') {
+            Err(InterpreterError::DivisionByZero { command, x, y }) => {
+                assert_eq!('/', command);
+                assert_eq!((0, 0), (x, y));
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
     }
 
     #[test]
-    fn command_execution_horizontal_if_not_zero() {
+    fn command_execution_modulus() {
         let mut source = String::from("@");
         let mut stdin = String::from("");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.stack.push(1);
-        interpreter.command_execution('_');
-        assert_eq!(Direction::Left, interpreter.pointer.direction());
+        interpreter.ip.stack.push(5);
+        interpreter.ip.stack.push(2);
+        interpreter.command_execution('%').unwrap();
+        assert_eq!(1, interpreter.ip.stack.pop());
     }
 
     #[test]
-    fn command_execution_vertical_if_zero() {
+    fn command_execution_modulus_by_zero_prompt() {
         let mut source = String::from("@");
-        let mut stdin = String::from("");
+        let mut stdin = String::from("3\n");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.stack.push(0);
-        interpreter.command_execution('|');
-        assert_eq!(Direction::Down, interpreter.pointer.direction());
+        interpreter.ip.stack.push(5);
+        interpreter.ip.stack.push(0);
+        interpreter.command_execution('%').unwrap();
+        assert_eq!(3, interpreter.ip.stack.pop());
+        assert_eq!(
+            "What do you want 5%0 to be? ",
+            str::from_utf8(&interpreter.stdout).unwrap()
+        );
     }
 
     #[test]
-    fn command_execution_vertical_if_not_zero() {
+    fn command_execution_modulus_by_zero_push_zero() {
         let mut source = String::from("@");
         let mut stdin = String::from("");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.stack.push(1);
-        interpreter.command_execution('|');
-        assert_eq!(Direction::Up, interpreter.pointer.direction());
+        interpreter.set_division_by_zero(DivisionByZero::PushZero);
+        interpreter.ip.stack.push(5);
+        interpreter.ip.stack.push(0);
+        interpreter.command_execution('%').unwrap();
+        assert_eq!(0, interpreter.ip.stack.pop());
     }
 
     #[test]
-    fn command_execution_string_mode() {
+    fn command_execution_modulus_by_zero_error() {
         let mut source = String::from("@");
         let mut stdin = String::from("");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.command_execution('"');
-        assert!(interpreter.string_mode);
+        interpreter.set_division_by_zero(DivisionByZero::Error);
+        interpreter.ip.stack.push(5);
+        interpreter.ip.stack.push(0);
+        assert!(interpreter.command_execution('%').is_err());
     }
 
     #[test]
-    fn command_execution_duplicate() {
+    fn command_execution_not_true() {
         let mut source = String::from("@");
         let mut stdin = String::from("");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.stack.push(2);
-        interpreter.command_execution(':');
-        assert_eq!(2, interpreter.stack.pop());
-        assert_eq!(2, interpreter.stack.pop());
+        interpreter.ip.stack.push(0);
+        interpreter.command_execution('!').unwrap();
+        assert_eq!(1, interpreter.ip.stack.pop());
     }
 
     #[test]
-    fn command_execution_swap() {
+    fn command_execution_not_false() {
         let mut source = String::from("@");
         let mut stdin = String::from("");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.stack.push(1);
-        interpreter.stack.push(2);
-        interpreter.command_execution('\\');
-        assert_eq!(1, interpreter.stack.pop());
-        assert_eq!(2, interpreter.stack.pop());
+        interpreter.ip.stack.push(78);
+        interpreter.command_execution('!').unwrap();
+        assert_eq!(0, interpreter.ip.stack.pop());
     }
 
     #[test]
-    fn command_execution_discard() {
+    fn command_execution_greater_than_greater() {
         let mut source = String::from("@");
         let mut stdin = String::from("");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.stack.push(1);
-        interpreter.command_execution('$');
-        assert_eq!(0, interpreter.stack.pop());
+        interpreter.ip.stack.push(99);
+        interpreter.ip.stack.push(0);
+        interpreter.command_execution('`').unwrap();
+        assert_eq!(1, interpreter.ip.stack.pop());
     }
 
     #[test]
-    fn command_execution_print_number() {
+    fn command_execution_greater_than_lesser() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.ip.stack.push(0);
+        interpreter.ip.stack.push(99);
+        interpreter.command_execution('`').unwrap();
+        assert_eq!(0, interpreter.ip.stack.pop());
+    }
+
+    #[test]
+    fn pointer_right() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.ip.pointer.set_direction(Direction::Down);
+        interpreter.command_execution('>').unwrap();
+        assert_eq!(Some(Direction::Right), interpreter.ip.pointer.direction());
+    }
+
+    #[test]
+    fn pointer_left() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.command_execution('<').unwrap();
+        assert_eq!(Some(Direction::Left), interpreter.ip.pointer.direction());
+    }
+
+    #[test]
+    fn pointer_up() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.ip.pointer.set_direction(Direction::Down);
+        interpreter.command_execution('^').unwrap();
+        assert_eq!(Some(Direction::Up), interpreter.ip.pointer.direction());
+    }
+
+    #[test]
+    fn pointer_down() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.ip.pointer.set_direction(Direction::Down);
+        interpreter.command_execution('v').unwrap();
+        assert_eq!(Some(Direction::Down), interpreter.ip.pointer.direction());
+    }
+
+    #[test]
+    fn pointer_random() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.set_direction_source(Box::new(ScriptedDirections::new(vec![
+            Direction::Left,
+            Direction::Up,
+        ])));
+        interpreter.ip.pointer.set_direction(Direction::Down);
+        interpreter.command_execution('?').unwrap();
+        assert_eq!(Some(Direction::Left), interpreter.ip.pointer.direction());
+        interpreter.command_execution('?').unwrap();
+        assert_eq!(Some(Direction::Up), interpreter.ip.pointer.direction());
+    }
+
+    #[test]
+    fn pointer_random_seeded() {
+        let mut first = Interpreter::from_source("", "".as_bytes(), vec![]).unwrap();
+        let mut second = Interpreter::from_source("", "".as_bytes(), vec![]).unwrap();
+        first.set_direction_source(Box::new(RandomDirections::seeded(7)));
+        second.set_direction_source(Box::new(RandomDirections::seeded(7)));
+        for _ in 0..20 {
+            first.command_execution('?').unwrap();
+            second.command_execution('?').unwrap();
+            assert_eq!(first.ip.pointer.direction(), second.ip.pointer.direction());
+        }
+    }
+
+    #[test]
+    fn command_execution_horizontal_if_zero() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.ip.stack.push(0);
+        interpreter.command_execution('_').unwrap();
+        assert_eq!(Some(Direction::Right), interpreter.ip.pointer.direction());
+    }
+
+    #[test]
+    fn command_execution_horizontal_if_not_zero() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.ip.stack.push(1);
+        interpreter.command_execution('_').unwrap();
+        assert_eq!(Some(Direction::Left), interpreter.ip.pointer.direction());
+    }
+
+    #[test]
+    fn command_execution_vertical_if_zero() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.ip.stack.push(0);
+        interpreter.command_execution('|').unwrap();
+        assert_eq!(Some(Direction::Down), interpreter.ip.pointer.direction());
+    }
+
+    #[test]
+    fn command_execution_vertical_if_not_zero() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.ip.stack.push(1);
+        interpreter.command_execution('|').unwrap();
+        assert_eq!(Some(Direction::Up), interpreter.ip.pointer.direction());
+    }
+
+    #[test]
+    fn command_execution_string_mode() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.command_execution('"').unwrap();
+        assert!(interpreter.ip.string_mode);
+    }
+
+    #[test]
+    fn command_execution_duplicate() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.ip.stack.push(2);
+        interpreter.command_execution(':').unwrap();
+        assert_eq!(2, interpreter.ip.stack.pop());
+        assert_eq!(2, interpreter.ip.stack.pop());
+    }
+
+    #[test]
+    fn command_execution_swap() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.ip.stack.push(1);
+        interpreter.ip.stack.push(2);
+        interpreter.command_execution('\\').unwrap();
+        assert_eq!(1, interpreter.ip.stack.pop());
+        assert_eq!(2, interpreter.ip.stack.pop());
+    }
+
+    #[test]
+    fn command_execution_discard() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.ip.stack.push(1);
+        interpreter.command_execution('$').unwrap();
+        assert_eq!(0, interpreter.ip.stack.pop());
+    }
+
+    #[test]
+    fn command_execution_print_number() {
         let mut source = String::from("@");
         let mut stdin = String::from("");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.command_execution('.');
-        println!("stdout contents: {:?}", stdout);
-        assert_eq!("1 ", str::from_utf8(&stdout).unwrap());
+        interpreter.ip.stack.push(1);
+        interpreter.command_execution('.').unwrap();
+        assert_eq!("1 ", str::from_utf8(&interpreter.stdout).unwrap());
     }
 
     #[test]
@@ -596,9 +1678,22 @@ mod tests {
         let mut stdin = String::from("");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.stack.push(65);
-        interpreter.command_execution(',');
-        assert_eq!("A", str::from_utf8(&stdout).unwrap());
+        interpreter.ip.stack.push(65);
+        interpreter.command_execution(',').unwrap();
+        assert_eq!("A", str::from_utf8(&interpreter.stdout).unwrap());
+    }
+
+    #[test]
+    fn command_execution_print_invalid_character() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.ip.stack.push(-1);
+        match interpreter.command_execution(',') {
+            Err(InterpreterError::InvalidCharacter(value)) => assert_eq!(-1, value),
+            other => panic!("Unexpected result {:?}", other),
+        }
     }
 
     #[test]
@@ -607,8 +1702,8 @@ mod tests {
         let mut stdin = String::from("");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.command_execution('#');
-        assert_eq!((0, 1), interpreter.pointer.coordinates());
+        interpreter.command_execution('#').unwrap();
+        assert_eq!((1, 0), interpreter.ip.pointer.coordinates());
     }
 
     #[test]
@@ -617,11 +1712,11 @@ mod tests {
         let mut stdin = String::from("");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.stack.push(65);
-        interpreter.stack.push(0);
-        interpreter.stack.push(0);
-        interpreter.command_execution('p');
-        assert_eq!('A', interpreter.source_matrix[0][0]);
+        interpreter.ip.stack.push(65);
+        interpreter.ip.stack.push(0);
+        interpreter.ip.stack.push(0);
+        interpreter.command_execution('p').unwrap();
+        assert_eq!(Some('A' as i32), interpreter.playfield.get(0, 0));
     }
 
     #[test]
@@ -630,10 +1725,51 @@ mod tests {
         let mut stdin = String::from("");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.stack.push(0);
-        interpreter.stack.push(0);
-        interpreter.command_execution('g');
-        assert_eq!(65, interpreter.stack.pop());
+        interpreter.ip.stack.push(0);
+        interpreter.ip.stack.push(0);
+        interpreter.command_execution('g').unwrap();
+        assert_eq!(65, interpreter.ip.stack.pop());
+    }
+
+    #[test]
+    fn command_execution_put_get_round_trip() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        for value in &[300, -1, i32::MAX, i32::MIN] {
+            interpreter.ip.stack.push(*value);
+            interpreter.ip.stack.push(1);
+            interpreter.ip.stack.push(2);
+            interpreter.command_execution('p').unwrap();
+            interpreter.ip.stack.push(1);
+            interpreter.ip.stack.push(2);
+            interpreter.command_execution('g').unwrap();
+            assert_eq!(*value, interpreter.ip.stack.pop());
+        }
+    }
+
+    #[test]
+    fn execute_stored_value() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.playfield.set(0, 0, '7' as i32);
+        interpreter.execute().unwrap();
+        assert_eq!(7, interpreter.ip.stack.pop());
+    }
+
+    #[test]
+    fn execute_string_mode_stored_value() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.playfield.set(0, 0, -1);
+        interpreter.ip.string_mode = true;
+        interpreter.execute().unwrap();
+        assert_eq!(-1, interpreter.ip.stack.pop());
     }
 
     #[test]
@@ -642,10 +1778,118 @@ mod tests {
         let mut stdin = String::from("");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.stack.push(100);
-        interpreter.stack.push(100);
-        interpreter.command_execution('g');
-        assert_eq!(0, interpreter.stack.pop());
+        interpreter.ip.stack.push(100);
+        interpreter.ip.stack.push(100);
+        interpreter.command_execution('g').unwrap();
+        assert_eq!(0, interpreter.ip.stack.pop());
+    }
+
+    #[test]
+    fn command_execution_put_coordinates() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.ip.stack.push(65);
+        interpreter.ip.stack.push(30);
+        interpreter.ip.stack.push(2);
+        interpreter.command_execution('p').unwrap();
+        assert_eq!(Some('A' as i32), interpreter.playfield.get(30, 2));
+    }
+
+    #[test]
+    fn command_execution_get_coordinates() {
+        let mut source = String::from("\n\n   B");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.ip.stack.push(3);
+        interpreter.ip.stack.push(2);
+        interpreter.command_execution('g').unwrap();
+        assert_eq!('B' as i32, interpreter.ip.stack.pop());
+    }
+
+    #[test]
+    fn command_execution_put_out_of_bounds_ignore() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        for (x, y) in &[(-1, 0), (0, -1), (80, 0), (0, 25)] {
+            interpreter.ip.stack.push(65);
+            interpreter.ip.stack.push(*x);
+            interpreter.ip.stack.push(*y);
+            interpreter.command_execution('p').unwrap();
+        }
+        assert!(interpreter.ip.stack.is_empty());
+        assert!(interpreter
+            .playfield
+            .iter()
+            .all(|(_, _, cell)| cell != 'A' as i32));
+    }
+
+    #[test]
+    fn command_execution_put_out_of_bounds_wrap() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.set_out_of_bounds(OutOfBounds::Wrap);
+        interpreter.ip.stack.push(65);
+        interpreter.ip.stack.push(-1);
+        interpreter.ip.stack.push(26);
+        interpreter.command_execution('p').unwrap();
+        assert_eq!(Some('A' as i32), interpreter.playfield.get(79, 1));
+    }
+
+    #[test]
+    fn command_execution_get_out_of_bounds_wrap() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.set_out_of_bounds(OutOfBounds::Wrap);
+        interpreter.ip.stack.push(80);
+        interpreter.ip.stack.push(-25);
+        interpreter.command_execution('g').unwrap();
+        assert_eq!('@' as i32, interpreter.ip.stack.pop());
+    }
+
+    #[test]
+    fn command_execution_put_out_of_bounds_error() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.set_out_of_bounds(OutOfBounds::Error);
+        interpreter.ip.stack.push(65);
+        interpreter.ip.stack.push(-3);
+        interpreter.ip.stack.push(100);
+        match interpreter.command_execution('p') {
+            Err(InterpreterError::OutOfBounds { command, x, y }) => {
+                assert_eq!('p', command);
+                assert_eq!((-3, 100), (x, y));
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn command_execution_get_out_of_bounds_error() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.set_out_of_bounds(OutOfBounds::Error);
+        interpreter.ip.stack.push(80);
+        interpreter.ip.stack.push(0);
+        match interpreter.command_execution('g') {
+            Err(InterpreterError::OutOfBounds { command, x, y }) => {
+                assert_eq!('g', command);
+                assert_eq!((80, 0), (x, y));
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
     }
 
     #[test]
@@ -654,8 +1898,90 @@ mod tests {
         let mut stdin = String::from("123\n");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.command_execution('&');
-        assert_eq!(123, interpreter.stack.pop());
+        interpreter.command_execution('&').unwrap();
+        assert_eq!(123, interpreter.ip.stack.pop());
+    }
+
+    #[test]
+    fn command_execution_read_number_skip_non_digits() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("abc 12 34\n");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.command_execution('&').unwrap();
+        assert_eq!(12, interpreter.ip.stack.pop());
+    }
+
+    #[test]
+    fn command_execution_read_number_negative() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("x-12\n");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.command_execution('&').unwrap();
+        assert_eq!(-12, interpreter.ip.stack.pop());
+    }
+
+    #[test]
+    fn command_execution_read_number_after_character() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("ab12\n");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.command_execution('~').unwrap();
+        interpreter.command_execution('~').unwrap();
+        interpreter.command_execution('&').unwrap();
+        interpreter.command_execution('~').unwrap();
+        assert_eq!('\n', interpreter.ip.stack.pop_char().unwrap());
+        assert_eq!(12, interpreter.ip.stack.pop());
+        assert_eq!('b', interpreter.ip.stack.pop_char().unwrap());
+        assert_eq!('a', interpreter.ip.stack.pop_char().unwrap());
+    }
+
+    #[test]
+    fn command_execution_read_number_skip_lines() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("none\n\n7\n");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.command_execution('&').unwrap();
+        assert_eq!(7, interpreter.ip.stack.pop());
+    }
+
+    #[test]
+    fn command_execution_read_number_too_large() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("99999999999\n");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        match interpreter.command_execution('&') {
+            Err(InterpreterError::InvalidInput(input)) => assert_eq!("99999999999", input),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn command_execution_read_number_eof() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("no digits");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.ip.stack.push(5);
+        interpreter.command_execution('&').unwrap();
+        assert_eq!(-1, interpreter.ip.stack.pop());
+        assert_eq!(5, interpreter.ip.stack.pop());
+    }
+
+    #[test]
+    fn command_execution_read_number_eof_reflect() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.set_end_of_input(EndOfInput::Reflect);
+        interpreter.command_execution('&').unwrap();
+        assert_eq!(Some(Direction::Left), interpreter.ip.pointer.direction());
+        assert!(interpreter.ip.stack.is_empty());
     }
 
     #[test]
@@ -664,41 +1990,836 @@ mod tests {
         let mut stdin = String::from("I\n");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.command_execution('~');
-        assert_eq!('I', interpreter.stack.pop_char());
+        interpreter.command_execution('~').unwrap();
+        assert_eq!('I', interpreter.ip.stack.pop_char().unwrap());
     }
 
     #[test]
-    fn command_execution_end() {
+    fn command_execution_read_character_whole_line() {
         let mut source = String::from("@");
-        let mut stdin = String::from("I\n");
+        let mut stdin = String::from("Hi\n");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.command_execution('@');
-        assert!(!interpreter.running);
-        assert!(interpreter.program_ended());
+        for _ in 0..3 {
+            interpreter.command_execution('~').unwrap();
+        }
+        assert_eq!(
+            vec!['H' as i32, 'i' as i32, '\n' as i32],
+            interpreter.ip.stack.values()
+        );
     }
 
     #[test]
-    fn command_execution_nop() {
+    fn command_execution_read_character_bytes() {
         let mut source = String::from("@");
-        let mut stdin = String::from("");
+        let mut stdin = String::from("é");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.command_execution(' ');
-        assert_eq!((0, 0), interpreter.pointer.coordinates());
-        assert_eq!(0, interpreter.stack.pop());
-        assert_eq!(Direction::Right, interpreter.pointer.direction());
-        assert_eq!('@', interpreter.source_matrix[0][0]);
+        interpreter.set_input_encoding(InputEncoding::Bytes);
+        interpreter.command_execution('~').unwrap();
+        interpreter.command_execution('~').unwrap();
+        assert_eq!(vec![0xC3, 0xA9], interpreter.ip.stack.values());
     }
 
     #[test]
-    #[should_panic]
-    fn command_execution_unknown_command() {
+    fn command_execution_read_character_eof() {
         let mut source = String::from("@");
         let mut stdin = String::from("");
         let mut stdout = vec![];
         let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
-        interpreter.command_execution('A');
+        interpreter.command_execution('~').unwrap();
+        assert_eq!(-1, interpreter.ip.stack.pop());
+    }
+
+    #[test]
+    fn command_execution_read_character_eof_reflect() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.set_end_of_input(EndOfInput::Reflect);
+        interpreter.ip.pointer.set_direction(Direction::Down);
+        interpreter.command_execution('~').unwrap();
+        assert_eq!(Some(Direction::Up), interpreter.ip.pointer.direction());
+        assert!(interpreter.ip.stack.is_empty());
+    }
+
+    #[test]
+    fn command_execution_end() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("I\n");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.command_execution('@').unwrap();
+        assert!(!interpreter.running);
+        assert!(interpreter.program_ended());
+    }
+
+    #[test]
+    fn command_execution_nop() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.command_execution(' ').unwrap();
+        assert_eq!((0, 0), interpreter.ip.pointer.coordinates());
+        assert_eq!(0, interpreter.ip.stack.pop());
+        assert_eq!(Some(Direction::Right), interpreter.ip.pointer.direction());
+        assert_eq!(Some('@' as i32), interpreter.playfield.get(0, 0));
+    }
+
+    #[test]
+    fn command_execution_unknown_command() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        match interpreter.command_execution('A') {
+            Err(InterpreterError::UnknownInstruction { command, x, y }) => {
+                assert_eq!('A', command);
+                assert_eq!((0, 0), (x, y));
+            }
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn command_execution_unknown_command_ignore() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.set_unknown_instruction(UnknownInstruction::Ignore);
+        interpreter.command_execution('A').unwrap();
+        assert_eq!(Some(Direction::Right), interpreter.ip.pointer.direction());
+        assert!(interpreter.ip.stack.is_empty());
+        assert_eq!(
+            &[Warning::UnknownInstruction {
+                command: 'A',
+                x: 0,
+                y: 0
+            }],
+            interpreter.warnings()
+        );
+    }
+
+    #[test]
+    fn command_execution_unknown_command_reflect() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.set_unknown_instruction(UnknownInstruction::Reflect);
+        interpreter.command_execution('A').unwrap();
+        assert_eq!(Some(Direction::Left), interpreter.ip.pointer.direction());
+        assert_eq!(1, interpreter.warnings().len());
+    }
+
+    #[test]
+    fn execute_unknown_command_warnings() {
+        let mut source = String::from("1A2B>#@ #<");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        interpreter.set_unknown_instruction(UnknownInstruction::Ignore);
+        for _ in 0..20 {
+            interpreter.execute().unwrap();
+        }
+        assert_eq!(
+            &[
+                Warning::UnknownInstruction {
+                    command: 'A',
+                    x: 1,
+                    y: 0
+                },
+                Warning::UnknownInstruction {
+                    command: 'B',
+                    x: 3,
+                    y: 0
+                }
+            ],
+            interpreter.warnings()
+        );
+    }
+
+    #[test]
+    fn command_execution_begin_block() {
+        let mut interpreter = new_funge98_interpreter("@ x");
+        interpreter.ip.stack.push(4);
+        interpreter.ip.stack.push(1);
+        interpreter.command_execution('{').unwrap();
+        assert_eq!(2, interpreter.stacks().depth());
+        assert_eq!(vec![4], interpreter.stack().values());
+        assert_eq!(vec![0, 0], interpreter.stacks().stacks()[0].values());
+        assert_eq!((1, 0), interpreter.storage_offset());
+    }
+
+    #[test]
+    fn command_execution_begin_block_edge() {
+        let mut interpreter = new_funge98_interpreter("ab@");
+        interpreter.ip.pointer.set_coordinates(2, 0);
+        interpreter.command_execution('{').unwrap();
+        assert_eq!((3, 0), interpreter.storage_offset());
+        interpreter.ip.pointer.set_coordinates(0, 0);
+        interpreter.ip.pointer.set_direction(Direction::Left);
+        interpreter.command_execution('{').unwrap();
+        assert_eq!((-1, 0), interpreter.storage_offset());
+    }
+
+    #[test]
+    fn command_execution_end_block() {
+        let mut interpreter = new_funge98_interpreter("@");
+        interpreter.ip.stack.push(0);
+        interpreter.command_execution('{').unwrap();
+        interpreter.ip.stack.push(0);
+        interpreter.command_execution('}').unwrap();
+        assert_eq!(1, interpreter.stacks().depth());
+        assert_eq!((0, 0), interpreter.storage_offset());
+    }
+
+    #[test]
+    fn command_execution_end_block_single_stack() {
+        let mut interpreter = new_funge98_interpreter("@");
+        interpreter.command_execution('}').unwrap();
+        assert_eq!(Some(Direction::Left), interpreter.ip.pointer.direction());
+    }
+
+    #[test]
+    fn command_execution_under_single_stack() {
+        let mut interpreter = new_funge98_interpreter("@");
+        interpreter.command_execution('u').unwrap();
+        assert_eq!(Some(Direction::Left), interpreter.ip.pointer.direction());
+    }
+
+    #[test]
+    fn command_execution_block_befunge93() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        match interpreter.command_execution('{') {
+            Err(InterpreterError::UnknownInstruction { command, .. }) => assert_eq!('{', command),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn storage_offset_put_get() {
+        let mut interpreter = new_funge98_interpreter("v\n>0{12p12g@");
+        while interpreter.execute().unwrap() == StepOutcome::Running {}
+        assert_eq!((3, 1), interpreter.storage_offset());
+        assert_eq!(Some(0), interpreter.playfield().get(4, 3));
+        assert_eq!(vec![0], interpreter.stack().values());
+    }
+
+    #[test]
+    fn command_execution_split() {
+        let mut interpreter = new_funge98_interpreter("@ x");
+        interpreter.ip.stack.push(3);
+        interpreter.command_execution('t').unwrap();
+        let ips: Vec<&Ip> = interpreter.ips().collect();
+        assert_eq!(2, ips.len());
+        assert_eq!(0, ips[0].id());
+        assert_eq!(1, ips[1].id());
+        assert_eq!((2, 0), ips[1].pointer().coordinates());
+        assert_eq!(Some(Direction::Left), ips[1].pointer().direction());
+        assert_eq!(vec![3], ips[1].stack().values());
+    }
+
+    #[test]
+    fn command_execution_split_befunge93() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        assert!(interpreter.command_execution('t').is_err());
+    }
+
+    #[test]
+    fn execute_concurrent_order() {
+        let mut interpreter = new_funge98_interpreter("t1.@@.2");
+        let mut order = vec![];
+        while interpreter.execute().unwrap() == StepOutcome::Running {
+            order.push(interpreter.ip().id());
+        }
+        assert_eq!(b"2 1 ", interpreter.output().as_slice());
+        assert_eq!(vec![1, 0, 1, 0, 1, 0], order);
+    }
+
+    #[test]
+    fn execute_end_kills_only_current_ip() {
+        let mut interpreter = new_funge98_interpreter("t@ 5");
+        interpreter.execute().unwrap();
+        interpreter.execute().unwrap();
+        assert_eq!(2, interpreter.ips().count());
+        assert_eq!(0, interpreter.ip().id());
+        assert_eq!(StepOutcome::Running, interpreter.execute().unwrap());
+        assert_eq!(1, interpreter.ips().count());
+        assert_eq!(1, interpreter.ip().id());
+    }
+
+    fn pinned_funge98_interpreter(source: &str) -> Interpreter<io::Empty, Vec<u8>> {
+        InterpreterBuilder::new()
+            .dialect(Dialect::Funge98)
+            .time(UNIX_EPOCH + Duration::from_secs(951_827_696))
+            .environment_variables(vec![("A".to_string(), "1".to_string())])
+            .arguments(vec!["prog.b98".to_string()])
+            .stdout(vec![])
+            .build(source.as_bytes())
+            .unwrap()
+    }
+
+    #[test]
+    fn command_execution_system_info() {
+        let mut interpreter = pinned_funge98_interpreter("@ \n  x");
+        interpreter.ip.stack.push(7);
+        interpreter.ip.stack.push(0);
+        interpreter.command_execution('y').unwrap();
+        let mut expected = vec![7];
+        expected.extend(&[0, 0, '1' as i32, '=' as i32, 'A' as i32]);
+        expected.extend(&[0, 0, 0, '8' as i32, '9' as i32, 'b' as i32, '.' as i32]);
+        expected.extend(&['g' as i32, 'o' as i32, 'r' as i32, 'p' as i32]);
+        expected.extend(&[1, 1]);
+        expected.extend(&[(12 << 16) + (34 << 8) + 56, (100 << 16) + (2 << 8) + 29]);
+        expected.extend(&[2, 1, 0, 0]);
+        expected.extend(&[0, 0, 1, 0, 0, 0]);
+        expected.extend(&[0, 0, 2, std::path::MAIN_SEPARATOR as i32, 0, 100]);
+        expected.extend(&[HANDPRINT, 4, 0x01]);
+        assert_eq!(expected.as_slice(), interpreter.stack().values());
+    }
+
+    #[test]
+    fn command_execution_system_info_far_apart_cells() {
+        let mut interpreter = new_funge98_interpreter("@");
+        interpreter.playfield.set(i32::MIN, i32::MIN, 'a' as i32);
+        interpreter.playfield.set(i32::MAX, i32::MAX, 'b' as i32);
+        let cells = interpreter.system_info();
+        let bounds = &cells[cells.len() - 19..cells.len() - 15];
+        assert_eq!(&[i32::MAX, i32::MAX, i32::MIN, i32::MIN], bounds);
+    }
+
+    #[test]
+    fn command_execution_system_info_no_variables() {
+        let interpreter = new_funge98_interpreter("@");
+        assert!(interpreter.system_info().starts_with(&[0, 0, 0]));
+    }
+
+    #[test]
+    fn command_execution_system_info_cell() {
+        let mut interpreter = pinned_funge98_interpreter("@");
+        interpreter.ip.stack.push(9);
+        interpreter.ip.stack.push(7);
+        interpreter.command_execution('y').unwrap();
+        assert_eq!(vec![9, 2], interpreter.stack().values());
+    }
+
+    #[test]
+    fn command_execution_system_info_pick() {
+        let mut interpreter = pinned_funge98_interpreter("@");
+        let cells = interpreter.system_info().len() as i32;
+        interpreter.ip.stack.push(5);
+        interpreter.ip.stack.push(6);
+        interpreter.ip.stack.push(cells + 2);
+        interpreter.command_execution('y').unwrap();
+        assert_eq!(vec![5, 6, 5], interpreter.stack().values());
+    }
+
+    #[test]
+    fn command_execution_system_info_befunge93() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        assert!(interpreter.command_execution('y').is_err());
+    }
+
+    #[test]
+    fn version() {
+        assert_eq!(100, super::version());
+    }
+
+    fn run_funge98(source: &str) -> Interpreter<io::Empty, Vec<u8>> {
+        let mut interpreter = new_funge98_interpreter(source);
+        let limits = Limits {
+            max_steps: Some(1000),
+            timeout: None,
+        };
+        assert_eq!(
+            RunOutcome::Halted,
+            interpreter.run_with_limits(limits).unwrap()
+        );
+        interpreter
+    }
+
+    #[test]
+    fn run_funge98_far_apart_cells() {
+        let interpreter = run_funge98("'A88888****:*0p'A088888****:*-1-0p@");
+        assert_eq!((1 << 31) + 2, interpreter.playfield().width());
+    }
+
+    #[test]
+    fn run_funge98_skips_to_far_cell() {
+        let interpreter = run_funge98("'@88888****:*0p");
+        assert!(interpreter.stack().is_empty());
+        assert_eq!((0, 0), interpreter.pointer().coordinates());
+    }
+
+    #[test]
+    fn command_execution_jump_forward() {
+        let interpreter = run_funge98("1j23@");
+        assert_eq!(vec![3], interpreter.stack().values());
+    }
+
+    #[test]
+    fn command_execution_jump_backward() {
+        let interpreter = run_funge98("v  @\n>5 ^ 06-j");
+        assert_eq!(vec![5], interpreter.stack().values());
+    }
+
+    #[test]
+    fn command_execution_iterate() {
+        let interpreter = run_funge98("3k4@");
+        assert_eq!(vec![4, 4, 4], interpreter.stack().values());
+    }
+
+    #[test]
+    fn command_execution_iterate_over_spaces() {
+        let interpreter = run_funge98("1112k ;x; +@");
+        assert_eq!(vec![3], interpreter.stack().values());
+    }
+
+    #[test]
+    fn command_execution_iterate_zero() {
+        let interpreter = run_funge98("0k5@");
+        assert!(interpreter.stack().is_empty());
+    }
+
+    #[test]
+    fn command_execution_iterate_moving_instruction() {
+        let interpreter = run_funge98("2k#@7@");
+        assert_eq!(vec![7], interpreter.stack().values());
+    }
+
+    #[test]
+    fn command_execution_jump_huge() {
+        let mut interpreter = new_funge98_interpreter("088888****:*:+1-j@");
+        let limits = Limits {
+            max_steps: Some(100),
+            timeout: None,
+        };
+        assert_eq!(
+            RunOutcome::OutOfFuel,
+            interpreter.run_with_limits(limits).unwrap()
+        );
+    }
+
+    #[test]
+    fn command_execution_iterate_counts_steps() {
+        let mut interpreter = new_funge98_interpreter("088888****:*:+1-k4@");
+        let limits = Limits {
+            max_steps: Some(1000),
+            timeout: None,
+        };
+        assert_eq!(
+            RunOutcome::OutOfFuel,
+            interpreter.run_with_limits(limits).unwrap()
+        );
+        assert!(interpreter.stack().len() < 1000);
+        assert_eq!((16, 0), interpreter.pointer().coordinates());
+    }
+
+    #[test]
+    fn command_execution_iterate_negative() {
+        let mut interpreter = new_funge98_interpreter("@");
+        interpreter.ip.stack.push(-1);
+        interpreter.command_execution('k').unwrap();
+        assert_eq!(Some(Direction::Left), interpreter.ip.pointer.direction());
+    }
+
+    #[test]
+    fn command_execution_absolute_delta() {
+        let mut interpreter = new_funge98_interpreter("@");
+        interpreter.ip.stack.push(2);
+        interpreter.ip.stack.push(-1);
+        interpreter.command_execution('x').unwrap();
+        assert_eq!((2, -1), interpreter.ip.pointer.delta());
+    }
+
+    #[test]
+    fn command_execution_turn() {
+        let mut interpreter = new_funge98_interpreter("@");
+        interpreter.command_execution('[').unwrap();
+        assert_eq!(Some(Direction::Up), interpreter.ip.pointer.direction());
+        interpreter.command_execution(']').unwrap();
+        interpreter.command_execution(']').unwrap();
+        assert_eq!(Some(Direction::Down), interpreter.ip.pointer.direction());
+        interpreter.command_execution('r').unwrap();
+        assert_eq!(Some(Direction::Up), interpreter.ip.pointer.direction());
+    }
+
+    #[test]
+    fn command_execution_compare() {
+        let mut interpreter = new_funge98_interpreter("@");
+        interpreter.ip.stack.push(1);
+        interpreter.ip.stack.push(2);
+        interpreter.command_execution('w').unwrap();
+        assert_eq!(Some(Direction::Up), interpreter.ip.pointer.direction());
+        interpreter.ip.stack.push(2);
+        interpreter.ip.stack.push(1);
+        interpreter.command_execution('w').unwrap();
+        assert_eq!(Some(Direction::Right), interpreter.ip.pointer.direction());
+        interpreter.ip.stack.push(1);
+        interpreter.ip.stack.push(1);
+        interpreter.command_execution('w').unwrap();
+        assert_eq!(Some(Direction::Right), interpreter.ip.pointer.direction());
+    }
+
+    #[test]
+    fn execute_comment_takes_no_time() {
+        let mut interpreter = new_funge98_interpreter("1;2 3;   4@");
+        interpreter.execute().unwrap();
+        interpreter.execute().unwrap();
+        assert_eq!(vec![1, 4], interpreter.stack().values());
+        assert_eq!(StepOutcome::Ended, interpreter.execute().unwrap());
+    }
+
+    #[test]
+    fn execute_spaces_only() {
+        let mut interpreter = new_funge98_interpreter("   ");
+        assert_eq!(StepOutcome::Running, interpreter.execute().unwrap());
+    }
+
+    #[test]
+    fn command_execution_fetch_character() {
+        let interpreter = run_funge98("'@@");
+        assert_eq!(vec!['@' as i32], interpreter.stack().values());
+    }
+
+    #[test]
+    fn command_execution_store_character() {
+        let interpreter = run_funge98("'@s 5@");
+        assert_eq!(Some('@' as i32), interpreter.playfield().get(3, 0));
+        assert_eq!(vec![5], interpreter.stack().values());
+    }
+
+    #[test]
+    fn command_execution_clear_stack() {
+        let interpreter = run_funge98("123nz@");
+        assert!(interpreter.stack().is_empty());
+    }
+
+    #[test]
+    fn command_execution_flow_control_befunge93() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        for command in "jkx[]rw;'snz".chars() {
+            assert!(interpreter.command_execution(command).is_err());
+        }
+    }
+
+    #[test]
+    fn command_execution_hex_digits() {
+        let mut interpreter = new_funge98_interpreter("@");
+        for command in "abcdef".chars() {
+            interpreter.command_execution(command).unwrap();
+        }
+        assert_eq!(vec![10, 11, 12, 13, 14, 15], interpreter.stack().values());
+    }
+
+    #[test]
+    fn command_execution_hex_digits_befunge93() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        assert!(interpreter.command_execution('a').is_err());
+    }
+
+    #[test]
+    fn command_execution_quit() {
+        let mut interpreter = new_funge98_interpreter("@");
+        interpreter.ip.stack.push(3);
+        interpreter.command_execution('q').unwrap();
+        assert!(interpreter.program_ended());
+        assert_eq!(Some(3), interpreter.exit_code());
+    }
+
+    #[test]
+    fn run_quit() {
+        let mut interpreter = new_funge98_interpreter("e1-q");
+        assert_eq!(RunOutcome::Quit(13), interpreter.run().unwrap());
+        assert_eq!(Some(13), interpreter.exit_code());
+    }
+
+    #[test]
+    fn run_quit_ends_every_ip() {
+        let mut interpreter = new_funge98_interpreter("t7q  >");
+        assert_eq!(RunOutcome::Quit(7), interpreter.run().unwrap());
+        assert_eq!(1, interpreter.ips().count());
+    }
+
+    #[test]
+    fn run_halted_without_exit_code() {
+        let mut interpreter = new_funge98_interpreter("@");
+        assert_eq!(RunOutcome::Halted, interpreter.run().unwrap());
+        assert_eq!(None, interpreter.exit_code());
+    }
+
+    /// A Funge-98 interpreter whose files live in a temporary root, removed
+    /// when the returned directory is dropped.
+    fn sandboxed_funge98_interpreter(
+        name: &str,
+        source: &str,
+    ) -> (Interpreter<io::Empty, Vec<u8>>, TempDir) {
+        let root = TempDir::new(&format!("files-{}", name));
+        let interpreter = InterpreterBuilder::new()
+            .dialect(Dialect::Funge98)
+            .sandbox(root.path())
+            .stdout(vec![])
+            .build(source.as_bytes())
+            .unwrap();
+        (interpreter, root)
+    }
+
+    fn push_string<I: BufRead, O: Write>(interpreter: &mut Interpreter<I, O>, string: &str) {
+        interpreter.ip.stack.push(0);
+        for character in string.chars().rev() {
+            interpreter.ip.stack.push_char(character);
+        }
+    }
+
+    #[test]
+    fn command_execution_output_input_file() {
+        let (mut interpreter, _root) = sandboxed_funge98_interpreter("round-trip", "@ab  \n cd");
+        interpreter.ip.stack.push(3);
+        interpreter.ip.stack.push(2);
+        interpreter.ip.stack.push(1);
+        interpreter.ip.stack.push(0);
+        interpreter.ip.stack.push(1);
+        push_string(&mut interpreter, "region.txt");
+        interpreter.command_execution('o').unwrap();
+        assert_eq!(Some(Direction::Right), interpreter.ip.pointer.direction());
+        assert!(interpreter.stack().is_empty());
+
+        interpreter.ip.storage_offset = (10, 10);
+        interpreter.ip.stack.push(0);
+        interpreter.ip.stack.push(5);
+        interpreter.ip.stack.push(0);
+        push_string(&mut interpreter, "region.txt");
+        interpreter.command_execution('i').unwrap();
+        assert_eq!(vec![2, 2, 0, 5], interpreter.stack().values());
+        assert_eq!(Some('a' as i32), interpreter.playfield().get(10, 15));
+        assert_eq!(Some('d' as i32), interpreter.playfield().get(11, 16));
+    }
+
+    #[test]
+    fn command_execution_input_file_binary() {
+        let (mut interpreter, root) = sandboxed_funge98_interpreter("binary", "@");
+        std::fs::write(root.path().join("data.bin"), b"x\ny").unwrap();
+        interpreter.ip.stack.push(0);
+        interpreter.ip.stack.push(1);
+        interpreter.ip.stack.push(1);
+        push_string(&mut interpreter, "data.bin");
+        interpreter.command_execution('i').unwrap();
+        assert_eq!(vec![3, 1, 0, 1], interpreter.stack().values());
+        assert_eq!(Some('\n' as i32), interpreter.playfield().get(1, 1));
+    }
+
+    #[test]
+    fn command_execution_file_outside_sandbox() {
+        let (mut interpreter, _root) = sandboxed_funge98_interpreter("outside", "@");
+        interpreter.ip.stack.push(0);
+        interpreter.ip.stack.push(0);
+        interpreter.ip.stack.push(0);
+        push_string(&mut interpreter, "../escape.txt");
+        interpreter.command_execution('i').unwrap();
+        assert_eq!(Some(Direction::Left), interpreter.ip.pointer.direction());
+    }
+
+    #[test]
+    fn command_execution_output_file_clipped() {
+        let (mut interpreter, _root) = sandboxed_funge98_interpreter("clipped", "@ab\n c");
+        for value in &[i32::MAX, i32::MAX, -5, -5, 1] {
+            interpreter.ip.stack.push(*value);
+        }
+        push_string(&mut interpreter, "clipped.txt");
+        interpreter.command_execution('o').unwrap();
+        assert_eq!(Some(Direction::Right), interpreter.ip.pointer.direction());
+        let contents = interpreter.sandbox.as_ref().unwrap().read("clipped.txt");
+        assert_eq!(b"@ab\n c\n".to_vec(), contents.unwrap());
+    }
+
+    #[test]
+    fn command_execution_invalid_string_reflects() {
+        let (mut interpreter, _root) = sandboxed_funge98_interpreter("invalid-string", "@");
+        interpreter.set_executor(Box::new(ScriptedExecutor::new(vec![("a".to_string(), 0)])));
+        for command in &['i', 'o', '='] {
+            interpreter.ip.pointer.set_direction(Direction::Right);
+            for value in &[1, 2, 3, 4, 5, 0, -1, 97] {
+                interpreter.ip.stack.push(*value);
+            }
+            interpreter.command_execution(*command).unwrap();
+            assert_eq!(Some(Direction::Left), interpreter.ip.pointer.direction());
+        }
+        assert_eq!(vec![1, 2, 1, 2, 3, 4, 5], interpreter.ip.stack.values());
+    }
+
+    #[test]
+    fn command_execution_file_access_disabled() {
+        let mut interpreter = new_funge98_interpreter("@");
+        for command in &['i', 'o'] {
+            interpreter.ip.pointer.set_direction(Direction::Right);
+            push_string(&mut interpreter, "file.txt");
+            interpreter.command_execution(*command).unwrap();
+            assert_eq!(Some(Direction::Left), interpreter.ip.pointer.direction());
+        }
+        for value in &[i32::MAX, i32::MAX, i32::MIN, i32::MIN, 0] {
+            interpreter.ip.stack.push(*value);
+        }
+        push_string(&mut interpreter, "file.txt");
+        interpreter.command_execution('o').unwrap();
+        assert_eq!(Some(Direction::Right), interpreter.ip.pointer.direction());
+        assert!(interpreter.ip.stack.is_empty());
+        assert_eq!(0x01, interpreter.flags());
+    }
+
+    #[test]
+    fn flags_file_access() {
+        let (interpreter, _root) = sandboxed_funge98_interpreter("flags", "@");
+        assert_eq!(0x07, interpreter.flags());
+    }
+
+    #[test]
+    fn command_execution_execute() {
+        let mut interpreter = new_funge98_interpreter("@");
+        interpreter.set_executor(Box::new(ScriptedExecutor::new(vec![(
+            "make all".to_string(),
+            2,
+        )])));
+        push_string(&mut interpreter, "make all");
+        interpreter.command_execution('=').unwrap();
+        assert_eq!(vec![2], interpreter.stack().values());
+        assert_eq!(0x09, interpreter.flags());
+    }
+
+    #[test]
+    fn command_execution_execute_refused() {
+        let mut interpreter = new_funge98_interpreter("@");
+        interpreter.set_executor(Box::new(ScriptedExecutor::new(vec![])));
+        push_string(&mut interpreter, "rm -rf /");
+        interpreter.command_execution('=').unwrap();
+        assert!(interpreter.stack().is_empty());
+        assert_eq!(Some(Direction::Left), interpreter.ip.pointer.direction());
+    }
+
+    #[test]
+    fn command_execution_execute_disabled() {
+        let mut interpreter = new_funge98_interpreter("@");
+        push_string(&mut interpreter, "true");
+        interpreter.command_execution('=').unwrap();
+        assert_eq!(Some(Direction::Left), interpreter.ip.pointer.direction());
+        assert_eq!(0x01, interpreter.flags());
+    }
+
+    fn push_fingerprint<I: BufRead, O: Write>(interpreter: &mut Interpreter<I, O>, name: &str) {
+        for character in name.chars().rev() {
+            interpreter.ip.stack.push_char(character);
+        }
+        interpreter.ip.stack.push(name.len() as i32);
+    }
+
+    #[test]
+    fn command_execution_load_fingerprint() {
+        let mut interpreter = new_funge98_interpreter("@");
+        interpreter.set_unknown_instruction(UnknownInstruction::Ignore);
+        push_fingerprint(&mut interpreter, "NULL");
+        interpreter.command_execution('(').unwrap();
+        assert_eq!(
+            vec![fingerprint_id("NULL"), 1],
+            interpreter.stack().values()
+        );
+        interpreter.command_execution('A').unwrap();
+        assert_eq!(Some(Direction::Left), interpreter.ip.pointer.direction());
+        assert!(interpreter.warnings().is_empty());
+    }
+
+    #[test]
+    fn command_execution_load_unknown_fingerprint() {
+        let mut interpreter = new_funge98_interpreter("@");
+        push_fingerprint(&mut interpreter, "NOPE");
+        interpreter.command_execution('(').unwrap();
+        assert!(interpreter.stack().is_empty());
+        assert_eq!(Some(Direction::Left), interpreter.ip.pointer.direction());
+        assert!(interpreter.ip.semantics().get('A').is_none());
+    }
+
+    #[test]
+    fn command_execution_unload_fingerprint() {
+        let mut interpreter = new_funge98_interpreter("@");
+        interpreter.set_unknown_instruction(UnknownInstruction::Ignore);
+        push_fingerprint(&mut interpreter, "NULL");
+        interpreter.command_execution('(').unwrap();
+        push_fingerprint(&mut interpreter, "NULL");
+        interpreter.command_execution(')').unwrap();
+        interpreter.command_execution('A').unwrap();
+        assert_eq!(Some(Direction::Right), interpreter.ip.pointer.direction());
+        assert_eq!(1, interpreter.warnings().len());
+        push_fingerprint(&mut interpreter, "NOPE");
+        interpreter.command_execution(')').unwrap();
+        assert_eq!(Some(Direction::Left), interpreter.ip.pointer.direction());
+    }
+
+    #[test]
+    fn command_execution_custom_fingerprint() {
+        let mut interpreter = new_funge98_interpreter("@");
+        interpreter.register_fingerprint(Box::new(Push));
+        push_fingerprint(&mut interpreter, "NULL");
+        interpreter.command_execution('(').unwrap();
+        push_fingerprint(&mut interpreter, "PUSH");
+        interpreter.command_execution('(').unwrap();
+        interpreter.ip.stack.clear();
+        interpreter.command_execution('P').unwrap();
+        assert_eq!(vec![7], interpreter.stack().values());
+        push_fingerprint(&mut interpreter, "PUSH");
+        interpreter.command_execution(')').unwrap();
+        interpreter.command_execution('P').unwrap();
+        assert_eq!(Some(Direction::Left), interpreter.ip.pointer.direction());
+    }
+
+    #[test]
+    fn command_execution_fingerprint_huge_count() {
+        let mut interpreter = new_funge98_interpreter("@");
+        interpreter.register_fingerprint(Box::new(Push));
+        interpreter.ip.stack.push(9);
+        push_fingerprint(&mut interpreter, "XPUSH");
+        interpreter.command_execution('(').unwrap();
+        assert_eq!(
+            vec![9, fingerprint_id("PUSH"), 1],
+            interpreter.stack().values()
+        );
+        push_fingerprint(&mut interpreter, "PUSH");
+        interpreter.ip.stack.pop();
+        interpreter.ip.stack.push(i32::MAX);
+        interpreter.command_execution(')').unwrap();
+        assert_eq!(Some(Direction::Left), interpreter.ip.pointer.direction());
+        assert!(interpreter.stack().is_empty());
+    }
+
+    #[test]
+    fn command_execution_fingerprint_befunge93() {
+        let mut source = String::from("@");
+        let mut stdin = String::from("");
+        let mut stdout = vec![];
+        let mut interpreter = new_interpreter(&mut source, &mut stdin, &mut stdout);
+        assert!(interpreter.command_execution('(').is_err());
+    }
+
+    #[test]
+    fn run_fingerprint() {
+        let interpreter = run_funge98("\"LLUN\"4($$#@A.@");
+        assert!(interpreter.stack().is_empty());
+        assert!(interpreter.warnings().is_empty());
     }
 }
@@ -0,0 +1,127 @@
+use crate::fingerprint::Semantics;
+use crate::pointer::Pointer;
+use crate::stack::{Stack, StackStack};
+
+/// An instruction pointer together with the state it owns. Befunge-93
+/// programs run a single IP; Funge-98 programs start more with `t`.
+#[derive(Debug, Clone)]
+pub struct Ip {
+    pub(crate) id: u64,
+    pub(crate) pointer: Pointer,
+    pub(crate) stack: StackStack,
+    pub(crate) storage_offset: (i32, i32),
+    pub(crate) string_mode: bool,
+    pub(crate) semantics: Semantics,
+    /// The `k` instructions still repeating, innermost last.
+    pub(crate) repeats: Vec<Repeat>,
+}
+
+/// A `k` instruction executing `command` once per tick, `remaining` more
+/// times. When done, an IP still at `start` with `delta` moves onto `target`.
+#[derive(Debug, Clone)]
+pub(crate) struct Repeat {
+    pub(crate) command: char,
+    pub(crate) remaining: u32,
+    pub(crate) start: (i32, i32),
+    pub(crate) delta: (i32, i32),
+    pub(crate) target: (i32, i32),
+}
+
+impl Ip {
+    /// Creates an IP with the given id at the origin, moving right.
+    pub fn new(id: u64) -> Self {
+        Ip {
+            id,
+            pointer: Pointer::default(),
+            stack: StackStack::default(),
+            storage_offset: (0, 0),
+            string_mode: false,
+            semantics: Semantics::default(),
+            repeats: vec![],
+        }
+    }
+
+    /// Implements `t`: a new IP with the given id and a reversed delta, with
+    /// a copy of the parent's position, stacks and storage offset.
+    pub fn split(&self, id: u64) -> Self {
+        let mut child = self.clone();
+        child.id = id;
+        child.repeats.clear();
+        child.pointer.reflect();
+        child
+    }
+
+    /// Identifies the IP for the lifetime of the program; ids are never reused.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    pub fn pointer(&self) -> &Pointer {
+        &self.pointer
+    }
+
+    /// The top of the stack stack.
+    pub fn stack(&self) -> &Stack {
+        &self.stack
+    }
+
+    pub fn stacks(&self) -> &StackStack {
+        &self.stack
+    }
+
+    pub fn stacks_mut(&mut self) -> &mut StackStack {
+        &mut self.stack
+    }
+
+    pub fn pointer_mut(&mut self) -> &mut Pointer {
+        &mut self.pointer
+    }
+
+    /// The offset added to the coordinates of `p` and `g`, set by `{` and `}`.
+    pub fn storage_offset(&self) -> (i32, i32) {
+        self.storage_offset
+    }
+
+    pub fn string_mode(&self) -> bool {
+        self.string_mode
+    }
+
+    /// The fingerprint semantics loaded with `(`.
+    pub fn semantics(&self) -> &Semantics {
+        &self.semantics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fingerprint::Null;
+    use crate::pointer::Direction;
+
+    #[test]
+    fn new() {
+        let ip = Ip::new(3);
+        assert_eq!(3, ip.id());
+        assert_eq!((0, 0), ip.pointer().coordinates());
+        assert_eq!(Some(Direction::Right), ip.pointer().direction());
+        assert!(ip.stack().is_empty());
+        assert_eq!((0, 0), ip.storage_offset());
+        assert!(!ip.string_mode());
+    }
+
+    #[test]
+    fn split() {
+        let mut ip = Ip::new(0);
+        ip.stack.push(5);
+        ip.storage_offset = (2, 3);
+        ip.semantics.load(&Null);
+        let child = ip.split(1);
+        assert_eq!(1, child.id());
+        assert_eq!(Some(Direction::Left), child.pointer().direction());
+        assert_eq!(vec![5], child.stack().values());
+        assert_eq!((2, 3), child.storage_offset());
+        assert!(child.semantics().get('A').is_some());
+        ip.stack.push(6);
+        assert_eq!(vec![5], child.stack().values());
+    }
+}
@@ -0,0 +1,41 @@
+//! bfng is a small and simple Befunge-93 interpreter.
+//!
+//! ```
+//! use bfng::Interpreter;
+//!
+//! let mut interpreter = Interpreter::from_source("\"!iH\",,,@", "".as_bytes(), vec![]).unwrap();
+//! interpreter.run().unwrap();
+//! assert_eq!(b"Hi!", interpreter.output().as_slice());
+//! ```
+
+extern crate rand;
+
+mod config;
+mod environment;
+mod error;
+mod executor;
+mod fingerprint;
+mod input;
+mod interpreter;
+mod ip;
+mod playfield;
+mod pointer;
+mod random;
+mod sandbox;
+mod stack;
+
+pub use config::{
+    Dialect, DivisionByZero, EndOfInput, InputEncoding, LoadMode, OutOfBounds, Overflow,
+    UnknownInstruction,
+};
+pub use error::{BuildError, ConfigError, InterpreterError, LoadError, Warning};
+pub use executor::{DisabledExecutor, Executor, ScriptedExecutor, SystemShell};
+pub use fingerprint::{
+    fingerprint_id, Fingerprint, FingerprintRegistry, Null, Semantic, Semantics,
+};
+pub use interpreter::{Interpreter, InterpreterBuilder, Limits, RunOutcome, StepOutcome};
+pub use ip::Ip;
+pub use playfield::Playfield;
+pub use pointer::{Direction, Pointer};
+pub use random::{DirectionSource, RandomDirections, ScriptedDirections};
+pub use stack::{Stack, StackStack};
@@ -1,26 +1,377 @@
+use bfng::{
+    Dialect, DivisionByZero, InterpreterBuilder, Limits, LoadMode, OutOfBounds, RunOutcome,
+    SystemShell, UnknownInstruction,
+};
 use std::{
     env,
     fs::File,
     io::{stdin, stdout, BufReader},
+    process,
+    time::Duration,
 };
 
-extern crate rand;
+const USAGE: &str = "Usage: bfng [--dialect befunge93|funge98] [--max-steps STEPS] [--timeout SECONDS] [--seed SEED]
+             [--division-by-zero prompt|zero|error]
+             [--out-of-bounds ignore|wrap|error] [--truncate]
+             [--width WIDTH] [--height HEIGHT]
+             [--unknown error|ignore|reflect] [--sandbox DIRECTORY] [--allow-shell] [--pass-env]
+             SOURCE [ARGUMENTS...]";
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    source: String,
+    arguments: Vec<String>,
+    dialect: Dialect,
+    limits: Limits,
+    seed: Option<u64>,
+    division_by_zero: Option<DivisionByZero>,
+    load_mode: LoadMode,
+    out_of_bounds: OutOfBounds,
+    width: Option<usize>,
+    height: Option<usize>,
+    unknown_instruction: Option<UnknownInstruction>,
+    sandbox: Option<String>,
+    allow_shell: bool,
+    pass_env: bool,
+}
 
-mod interpreter;
-mod pointer;
-mod stack;
-use interpreter::*;
+fn parse_args<A: Iterator<Item = String>>(mut args: A) -> Result<Options, String> {
+    let mut source = None;
+    let mut arguments = vec![];
+    let mut dialect = Dialect::default();
+    let mut limits = Limits::default();
+    let mut seed = None;
+    let mut division_by_zero = None;
+    let mut load_mode = LoadMode::default();
+    let mut out_of_bounds = OutOfBounds::default();
+    let mut width = None;
+    let mut height = None;
+    let mut unknown_instruction = None;
+    let mut sandbox = None;
+    let mut allow_shell = false;
+    let mut pass_env = false;
+    while let Some(arg) = args.next() {
+        if source.is_some() {
+            arguments.push(arg);
+            continue;
+        }
+        match arg.as_str() {
+            "--dialect" => {
+                let value = args.next().ok_or("--dialect requires a value")?;
+                dialect = match value.as_str() {
+                    "befunge93" => Dialect::Befunge93,
+                    "funge98" => Dialect::Funge98,
+                    _ => return Err(format!("Invalid dialect '{}'", value)),
+                };
+            }
+            "--max-steps" => {
+                let value = args.next().ok_or("--max-steps requires a value")?;
+                let max_steps = value
+                    .parse::<u64>()
+                    .map_err(|_| format!("Invalid step count '{}'", value))?;
+                limits.max_steps = Some(max_steps);
+            }
+            "--timeout" => {
+                let value = args.next().ok_or("--timeout requires a value")?;
+                let timeout = value
+                    .parse::<f64>()
+                    .ok()
+                    .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+                    .ok_or(format!("Invalid timeout '{}'", value))?;
+                limits.timeout = Some(timeout);
+            }
+            "--seed" => {
+                let value = args.next().ok_or("--seed requires a value")?;
+                let parsed = value
+                    .parse::<u64>()
+                    .map_err(|_| format!("Invalid seed '{}'", value))?;
+                seed = Some(parsed);
+            }
+            "--division-by-zero" => {
+                let value = args.next().ok_or("--division-by-zero requires a value")?;
+                division_by_zero = Some(match value.as_str() {
+                    "prompt" => DivisionByZero::Prompt,
+                    "zero" => DivisionByZero::PushZero,
+                    "error" => DivisionByZero::Error,
+                    _ => return Err(format!("Invalid division by zero policy '{}'", value)),
+                });
+            }
+            "--out-of-bounds" => {
+                let value = args.next().ok_or("--out-of-bounds requires a value")?;
+                out_of_bounds = match value.as_str() {
+                    "ignore" => OutOfBounds::Ignore,
+                    "wrap" => OutOfBounds::Wrap,
+                    "error" => OutOfBounds::Error,
+                    _ => return Err(format!("Invalid out of bounds policy '{}'", value)),
+                };
+            }
+            "--width" => width = Some(parse_dimension("--width", args.next())?),
+            "--height" => height = Some(parse_dimension("--height", args.next())?),
+            "--unknown" => {
+                let value = args.next().ok_or("--unknown requires a value")?;
+                unknown_instruction = Some(match value.as_str() {
+                    "error" => UnknownInstruction::Error,
+                    "ignore" => UnknownInstruction::Ignore,
+                    "reflect" => UnknownInstruction::Reflect,
+                    _ => return Err(format!("Invalid unknown instruction mode '{}'", value)),
+                });
+            }
+            "--sandbox" => {
+                sandbox = Some(args.next().ok_or("--sandbox requires a directory")?);
+            }
+            "--truncate" => load_mode = LoadMode::Truncate,
+            "--allow-shell" => allow_shell = true,
+            "--pass-env" => pass_env = true,
+            _ if arg.starts_with("--") => return Err(format!("Unknown option '{}'", arg)),
+            _ => source = Some(arg),
+        }
+    }
+    let source = source.ok_or("Please specify source")?;
+    Ok(Options {
+        source,
+        arguments,
+        dialect,
+        limits,
+        seed,
+        division_by_zero,
+        load_mode,
+        out_of_bounds,
+        width,
+        height,
+        unknown_instruction,
+        sandbox,
+        allow_shell,
+        pass_env,
+    })
+}
+
+fn parse_dimension(option: &str, value: Option<String>) -> Result<usize, String> {
+    let value = value.ok_or(format!("{} requires a value", option))?;
+    value
+        .parse::<usize>()
+        .ok()
+        .filter(|dimension| *dimension > 0)
+        .ok_or(format!("Invalid {} '{}'", &option[2..], value))
+}
 
 fn main() {
-    if let Some(file_name) = env::args().nth(1) {
-        let file = File::open(file_name).unwrap();
-        let source = BufReader::new(file);
-        let stdin_buf = BufReader::new(stdin());
-        let mut interpreter = Interpreter::new(source, stdin_buf, stdout());
-        while !interpreter.program_ended() {
-            interpreter.execute();
-        }
-    } else {
-        println!("Plase specify source");
+    let options = match parse_args(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{}\n{}", message, USAGE);
+            process::exit(2);
+        }
+    };
+    let file = match File::open(&options.source) {
+        Ok(file) => file,
+        Err(error) => {
+            eprintln!("Cannot open '{}': {}", options.source, error);
+            process::exit(1);
+        }
+    };
+    let stdin_buf = BufReader::new(stdin());
+    let source = BufReader::new(file);
+    let mut builder = InterpreterBuilder::new()
+        .stdin(stdin_buf)
+        .stdout(stdout())
+        .dialect(options.dialect)
+        .arguments(
+            std::iter::once(options.source.clone())
+                .chain(options.arguments)
+                .collect(),
+        )
+        .load_mode(options.load_mode)
+        .out_of_bounds(options.out_of_bounds)
+        .limits(options.limits);
+    // Policies left unset fall back to the defaults of the dialect.
+    if let Some(division_by_zero) = options.division_by_zero {
+        builder = builder.division_by_zero(division_by_zero);
+    }
+    if let Some(unknown_instruction) = options.unknown_instruction {
+        builder = builder.unknown_instruction(unknown_instruction);
+    }
+    if options.width.is_some() || options.height.is_some() {
+        builder = builder.dimensions(options.width.unwrap_or(80), options.height.unwrap_or(25));
+    }
+    if let Some(sandbox) = options.sandbox {
+        builder = builder.sandbox(sandbox);
+    }
+    if options.pass_env {
+        builder = builder.inherit_environment_variables();
+    }
+    if options.allow_shell {
+        builder = builder.executor(Box::new(SystemShell));
+    }
+    if let Some(seed) = options.seed {
+        builder = builder.seed(seed);
+    }
+    let mut interpreter = match builder.build(source) {
+        Ok(interpreter) => interpreter,
+        Err(error) => {
+            eprintln!("Cannot load '{}': {}", options.source, error);
+            process::exit(1);
+        }
+    };
+    for warning in interpreter.warnings() {
+        eprintln!("Warning: {}", warning);
+    }
+    let load_warnings = interpreter.warnings().len();
+    let result = interpreter.run();
+    for warning in &interpreter.warnings()[load_warnings..] {
+        eprintln!("Warning: {}", warning);
+    }
+    match result {
+        Ok(RunOutcome::Halted) => (),
+        Ok(RunOutcome::Quit(exit_code)) => process::exit(exit_code),
+        Ok(RunOutcome::OutOfFuel) => {
+            eprintln!("Step limit reached before the program ended");
+            process::exit(3);
+        }
+        Ok(RunOutcome::TimedOut) => {
+            eprintln!("Timeout reached before the program ended");
+            process::exit(3);
+        }
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> impl Iterator<Item = String> {
+        args.iter()
+            .map(|arg| arg.to_string())
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    #[test]
+    fn parse_args_source() {
+        let options = parse_args(args(&["program.bf"])).unwrap();
+        assert_eq!("program.bf", options.source);
+        assert_eq!(Limits::default(), options.limits);
+        assert_eq!(None, options.seed);
+    }
+
+    #[test]
+    fn parse_args_dialect() {
+        let options = parse_args(args(&["--dialect", "funge98", "program.bf"])).unwrap();
+        assert_eq!(Dialect::Funge98, options.dialect);
+        assert_eq!((None, None), (options.width, options.height));
+        assert_eq!(None, options.division_by_zero);
+        assert_eq!(None, options.unknown_instruction);
+        assert!(parse_args(args(&["--dialect", "befunge97", "program.bf"])).is_err());
+    }
+
+    #[test]
+    fn parse_args_program_arguments() {
+        let options = parse_args(args(&["program.bf", "one", "--seed"])).unwrap();
+        assert_eq!("program.bf", options.source);
+        assert_eq!(vec!["one", "--seed"], options.arguments);
+        assert_eq!(None, options.seed);
+    }
+
+    #[test]
+    fn parse_args_seed() {
+        let options = parse_args(args(&["--seed", "42", "program.bf"])).unwrap();
+        assert_eq!(Some(42), options.seed);
+    }
+
+    #[test]
+    fn parse_args_division_by_zero() {
+        let options = parse_args(args(&["--division-by-zero", "zero", "program.bf"])).unwrap();
+        assert_eq!(Some(DivisionByZero::PushZero), options.division_by_zero);
+        assert!(parse_args(args(&["--division-by-zero", "nan", "program.bf"])).is_err());
+    }
+
+    #[test]
+    fn parse_args_out_of_bounds() {
+        let options = parse_args(args(&["--out-of-bounds", "wrap", "program.bf"])).unwrap();
+        assert_eq!(OutOfBounds::Wrap, options.out_of_bounds);
+        assert!(parse_args(args(&["--out-of-bounds", "clamp", "program.bf"])).is_err());
+    }
+
+    #[test]
+    fn parse_args_dimensions() {
+        let options =
+            parse_args(args(&["--width", "120", "--height", "40", "program.bf"])).unwrap();
+        assert_eq!((Some(120), Some(40)), (options.width, options.height));
+        assert!(parse_args(args(&["--width", "0", "program.bf"])).is_err());
+        assert!(parse_args(args(&["--height", "tall", "program.bf"])).is_err());
+    }
+
+    #[test]
+    fn parse_args_unknown_instruction() {
+        let options = parse_args(args(&["--unknown", "reflect", "program.bf"])).unwrap();
+        assert_eq!(
+            Some(UnknownInstruction::Reflect),
+            options.unknown_instruction
+        );
+        assert!(parse_args(args(&["--unknown", "skip", "program.bf"])).is_err());
+    }
+
+    #[test]
+    fn parse_args_sandbox() {
+        let options = parse_args(args(&["--sandbox", "files", "program.bf"])).unwrap();
+        assert_eq!(Some("files".to_string()), options.sandbox);
+        assert!(parse_args(args(&["program.bf"])).unwrap().sandbox.is_none());
+        assert!(parse_args(args(&["--sandbox"])).is_err());
+    }
+
+    #[test]
+    fn parse_args_allow_shell() {
+        assert!(
+            parse_args(args(&["--allow-shell", "program.bf"]))
+                .unwrap()
+                .allow_shell
+        );
+        assert!(!parse_args(args(&["program.bf"])).unwrap().allow_shell);
+    }
+
+    #[test]
+    fn parse_args_truncate() {
+        let options = parse_args(args(&["--truncate", "program.bf"])).unwrap();
+        assert_eq!(LoadMode::Truncate, options.load_mode);
+    }
+
+    #[test]
+    fn parse_args_limits() {
+        let options = parse_args(args(&[
+            "--max-steps",
+            "10",
+            "--timeout",
+            "1.5",
+            "program.bf",
+        ]))
+        .unwrap();
+        assert_eq!(Some(10), options.limits.max_steps);
+        assert_eq!(Some(Duration::from_millis(1500)), options.limits.timeout);
+    }
+
+    #[test]
+    fn parse_args_missing_source() {
+        assert!(parse_args(args(&["--max-steps", "10"])).is_err());
+    }
+
+    #[test]
+    fn parse_args_invalid_value() {
+        assert!(parse_args(args(&["--max-steps", "ten", "program.bf"])).is_err());
+        assert!(parse_args(args(&["--timeout", "-1", "program.bf"])).is_err());
+        assert!(parse_args(args(&["--timeout", "1e20", "program.bf"])).is_err());
+        assert!(parse_args(args(&["--timeout", "inf", "program.bf"])).is_err());
+    }
+
+    #[test]
+    fn parse_args_pass_env() {
+        assert!(
+            parse_args(args(&["--pass-env", "program.bf"]))
+                .unwrap()
+                .pass_env
+        );
+        assert!(!parse_args(args(&["program.bf"])).unwrap().pass_env);
     }
 }
@@ -0,0 +1,919 @@
+use crate::config::LoadMode;
+use crate::error::{LoadError, Warning};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{self, BufRead, Write};
+
+const SPACE: i32 = ' ' as i32;
+
+/// The grid holding the program. Coordinates are `(x, y)`, with `x` growing
+/// to the right and `y` growing downwards from the top left corner.
+///
+/// A playfield is either a fixed Befunge-93 grid, wrapping around its edges,
+/// or an unbounded Funge-98 space with signed coordinates, wrapping around
+/// the edges of its bounding box (Lahey-space).
+#[derive(Debug, PartialEq, Clone)]
+pub struct Playfield {
+    cells: Cells,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Cells {
+    Bounded {
+        width: usize,
+        height: usize,
+        cells: Vec<i32>,
+    },
+    Unbounded {
+        cells: HashMap<(i32, i32), i32>,
+        bounds: Option<Bounds>,
+    },
+}
+
+type Bounds = ((i32, i32), (i32, i32));
+
+impl Default for Playfield {
+    /// The 80x25 playfield of Befunge-93.
+    fn default() -> Self {
+        Playfield::new(80, 25)
+    }
+}
+
+impl Playfield {
+    /// Creates a playfield filled with spaces.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either dimension is zero.
+    pub fn new(width: usize, height: usize) -> Self {
+        assert!(
+            width > 0 && height > 0,
+            "Playfield dimensions must be positive"
+        );
+        Playfield {
+            cells: Cells::Bounded {
+                width,
+                height,
+                cells: vec![SPACE; width * height],
+            },
+        }
+    }
+
+    /// Creates an empty Funge-98 space. Every coordinate holds a space until
+    /// written, and the space grows as cells outside it are written.
+    pub fn unbounded() -> Self {
+        Playfield {
+            cells: Cells::Unbounded {
+                cells: HashMap::new(),
+                bounds: None,
+            },
+        }
+    }
+
+    pub fn is_unbounded(&self) -> bool {
+        match self.cells {
+            Cells::Bounded { .. } => false,
+            Cells::Unbounded { .. } => true,
+        }
+    }
+
+    /// Writes the program read from `source` into the playfield, starting at
+    /// the top left corner. Trailing spaces and empty lines past the edges
+    /// are always accepted; anything else is handled according to `load_mode`.
+    /// Unbounded playfields accept programs of any size.
+    pub fn load<S: BufRead>(
+        &mut self,
+        source: S,
+        load_mode: LoadMode,
+    ) -> Result<Vec<Warning>, LoadError> {
+        let mut warnings = vec![];
+        for (y, line) in source.lines().enumerate() {
+            for (x, command) in line?.chars().enumerate() {
+                if command == ' ' || self.set(x as i32, y as i32, command as i32) {
+                    continue;
+                }
+                let (line, column) = (y + 1, x + 1);
+                match load_mode {
+                    LoadMode::Strict => return Err(LoadError::OutsidePlayfield { line, column }),
+                    LoadMode::Truncate => {
+                        warnings.push(Warning::Truncated { line, column });
+                        break;
+                    }
+                }
+            }
+        }
+        Ok(warnings)
+    }
+
+    /// The number of columns, or the width of the bounding box of an
+    /// unbounded playfield.
+    pub fn width(&self) -> usize {
+        match &self.cells {
+            Cells::Bounded { width, .. } => *width,
+            Cells::Unbounded { bounds, .. } => {
+                bounds.map_or(0, |((min_x, _), (max_x, _))| extent(min_x, max_x))
+            }
+        }
+    }
+
+    /// The number of rows, or the height of the bounding box of an
+    /// unbounded playfield.
+    pub fn height(&self) -> usize {
+        match &self.cells {
+            Cells::Bounded { height, .. } => *height,
+            Cells::Unbounded { bounds, .. } => {
+                bounds.map_or(0, |((_, min_y), (_, max_y))| extent(min_y, max_y))
+            }
+        }
+    }
+
+    /// Whether `(x, y)` can be read and written. Always true for unbounded
+    /// playfields.
+    pub fn in_bounds(&self, x: i32, y: i32) -> bool {
+        match &self.cells {
+            Cells::Bounded { width, height, .. } => {
+                x >= 0 && y >= 0 && (x as usize) < *width && (y as usize) < *height
+            }
+            Cells::Unbounded { .. } => true,
+        }
+    }
+
+    /// Maps any coordinates onto the playfield by wrapping them around its
+    /// edges. Unbounded playfields hold every coordinate, which is returned
+    /// unchanged.
+    pub fn wrap(&self, x: i32, y: i32) -> (i32, i32) {
+        match &self.cells {
+            Cells::Bounded { width, height, .. } => {
+                (x.rem_euclid(*width as i32), y.rem_euclid(*height as i32))
+            }
+            Cells::Unbounded { .. } => (x, y),
+        }
+    }
+
+    /// The position reached by moving from `position` by `delta`.
+    ///
+    /// Bounded playfields wrap around their edges. Unbounded playfields
+    /// wrap in Lahey-space: leaving the bounding box, the pointer travels
+    /// back along `delta` to the opposite edge of the box. A pointer outside
+    /// the box whose path crosses it is brought to the first cell of the box
+    /// on that path; one whose path misses the box keeps moving.
+    pub fn advance(&self, position: (i32, i32), delta: (i32, i32)) -> (i32, i32) {
+        let (x, y) = position;
+        let (dx, dy) = delta;
+        let next = (x.wrapping_add(dx), y.wrapping_add(dy));
+        let bounds = match &self.cells {
+            Cells::Bounded { .. } => return self.wrap(next.0, next.1),
+            Cells::Unbounded { bounds, .. } => *bounds,
+        };
+        match bounds.and_then(|bounds| crossing(bounds, position, delta)) {
+            Some((first, last)) if first > 1 || last < 1 => step(position, delta, first),
+            _ => next,
+        }
+    }
+
+    /// The position reached by calling [`advance`](Playfield::advance)
+    /// `steps` times, computed without walking the path.
+    pub fn advance_by(&self, position: (i32, i32), delta: (i32, i32), steps: u32) -> (i32, i32) {
+        if steps == 0 || delta == (0, 0) {
+            return position;
+        }
+        let bounds = match &self.cells {
+            Cells::Bounded { width, height, .. } => {
+                let (x, y) = (
+                    i64::from(position.0) + i64::from(steps) * i64::from(delta.0),
+                    i64::from(position.1) + i64::from(steps) * i64::from(delta.1),
+                );
+                return (
+                    x.rem_euclid(*width as i64) as i32,
+                    y.rem_euclid(*height as i64) as i32,
+                );
+            }
+            Cells::Unbounded { bounds, .. } => *bounds,
+        };
+        // After one step the pointer is either inside the bounding box,
+        // cycling along its path through it, or on a path missing the box.
+        let position = self.advance(position, delta);
+        let steps = i64::from(steps - 1);
+        match bounds.and_then(|bounds| crossing(bounds, position, delta)) {
+            Some((first, last)) if first <= 0 && last >= 0 => step(
+                position,
+                delta,
+                first + (steps - first).rem_euclid(last - first + 1),
+            ),
+            _ => step(position, delta, steps),
+        }
+    }
+
+    /// Moves from `position` by `delta` like [`advance`](Playfield::advance),
+    /// jumping over the empty cells of an unbounded playfield straight to the
+    /// next cell holding something other than a space. Returns `None` when
+    /// there is no such cell on the path.
+    pub fn skip_spaces(&self, position: (i32, i32), delta: (i32, i32)) -> Option<(i32, i32)> {
+        let next = self.advance(position, delta);
+        let cells = match &self.cells {
+            Cells::Bounded { .. } => return Some(next),
+            Cells::Unbounded { cells, .. } => cells,
+        };
+        if delta == (0, 0) || cells.contains_key(&next) {
+            return Some(next);
+        }
+        // Cells on the path ahead are reached first, in order; otherwise the
+        // pointer wraps around to the earliest cell behind it.
+        let steps = cells
+            .keys()
+            .filter_map(|cell| steps_to(position, delta, *cell))
+            .map(|steps| (steps < 1, steps))
+            .min()?;
+        Some(step(position, delta, steps.1))
+    }
+
+    /// The number of cells holding something other than a space.
+    pub fn occupied(&self) -> usize {
+        match &self.cells {
+            Cells::Bounded { cells, .. } => cells.iter().filter(|value| **value != SPACE).count(),
+            Cells::Unbounded { cells, .. } => cells.len(),
+        }
+    }
+
+    /// The value of the cell at `(x, y)`, or `None` outside the playfield.
+    pub fn get(&self, x: i32, y: i32) -> Option<i32> {
+        match &self.cells {
+            Cells::Bounded { width, cells, .. } if self.in_bounds(x, y) => {
+                Some(cells[y as usize * width + x as usize])
+            }
+            Cells::Bounded { .. } => None,
+            Cells::Unbounded { cells, .. } => Some(*cells.get(&(x, y)).unwrap_or(&SPACE)),
+        }
+    }
+
+    /// Stores `value` at `(x, y)`. Returns `false`, leaving the playfield
+    /// untouched, when the coordinates are outside of it. Unbounded
+    /// playfields grow to include `(x, y)` unless `value` is a space.
+    pub fn set(&mut self, x: i32, y: i32, value: i32) -> bool {
+        let in_bounds = self.in_bounds(x, y);
+        match &mut self.cells {
+            Cells::Bounded { width, cells, .. } if in_bounds => {
+                cells[y as usize * *width + x as usize] = value;
+                true
+            }
+            Cells::Bounded { .. } => false,
+            Cells::Unbounded { cells, bounds } => {
+                if value == SPACE {
+                    cells.remove(&(x, y));
+                } else {
+                    cells.insert((x, y), value);
+                    *bounds = Some(grow(*bounds, (x, y)));
+                }
+                true
+            }
+        }
+    }
+
+    /// Iterates over cells as `(x, y, value)`, row by row. Bounded
+    /// playfields yield every cell; unbounded playfields only yield the
+    /// non-space cells stored in them, however far apart.
+    pub fn iter(&self) -> Box<dyn Iterator<Item = (i32, i32, i32)> + '_> {
+        match &self.cells {
+            Cells::Bounded { width, height, .. } => {
+                let (width, height) = (*width as i32, *height as i32);
+                Box::new((0..height).flat_map(move |y| {
+                    (0..width).map(move |x| (x, y, self.get(x, y).unwrap_or(SPACE)))
+                }))
+            }
+            Cells::Unbounded { cells, .. } => {
+                let mut stored: Vec<(i32, i32, i32)> = cells
+                    .iter()
+                    .filter(|(_, value)| **value != SPACE)
+                    .map(|((x, y), value)| (*x, *y, *value))
+                    .collect();
+                stored.sort_unstable_by_key(|(x, y, _)| (*y, *x));
+                Box::new(stored.into_iter())
+            }
+        }
+    }
+
+    /// The top left and bottom right corners of the smallest rectangle
+    /// holding every non-space cell, or `None` if the playfield is empty.
+    ///
+    /// The box of an unbounded playfield only grows: cells later overwritten
+    /// with spaces are still included.
+    pub fn bounding_box(&self) -> Option<Bounds> {
+        match &self.cells {
+            Cells::Bounded { .. } => self
+                .iter()
+                .filter(|(_, _, value)| *value != SPACE)
+                .fold(None, |bounds, (x, y, _)| Some(grow(bounds, (x, y)))),
+            Cells::Unbounded { bounds, .. } => *bounds,
+        }
+    }
+
+    /// Writes `contents` with its top left corner at `origin`, as `i` does,
+    /// and returns the size of the rectangle it covers. Text is split into
+    /// lines, while binary contents are written byte by byte on a single
+    /// line. Spaces leave the cells below them untouched, and cells falling
+    /// outside a bounded playfield are dropped.
+    pub fn load_at(&mut self, origin: (i32, i32), contents: &[u8], binary: bool) -> (i32, i32) {
+        let lines: Vec<Vec<i32>> = if binary {
+            vec![contents.iter().map(|byte| i32::from(*byte)).collect()]
+        } else {
+            String::from_utf8_lossy(contents)
+                .lines()
+                .map(|line| line.chars().map(|character| character as i32).collect())
+                .collect()
+        };
+        let (x, y) = origin;
+        for (row, line) in lines.iter().enumerate() {
+            for (column, value) in line.iter().enumerate() {
+                if *value != SPACE {
+                    self.set(
+                        x.wrapping_add(column as i32),
+                        y.wrapping_add(row as i32),
+                        *value,
+                    );
+                }
+            }
+        }
+        let width = lines.iter().map(|line| line.len()).max().unwrap_or(0);
+        (width as i32, lines.len() as i32)
+    }
+
+    /// Writes the rectangle of `size` cells starting at `origin` to
+    /// `output`, as `o` does, one line per row. The rectangle is clipped to
+    /// the playfield, or to the bounding box of an unbounded playfield, and
+    /// streamed cell by cell. In text mode trailing spaces and trailing
+    /// empty lines are left out. Values that are not characters are written
+    /// as U+FFFD.
+    pub fn write_region<W: Write>(
+        &self,
+        origin: (i32, i32),
+        size: (i32, i32),
+        text: bool,
+        output: &mut W,
+    ) -> io::Result<()> {
+        let ((min_x, min_y), (max_x, max_y)) = match &self.cells {
+            Cells::Bounded { width, height, .. } => {
+                ((0, 0), (*width as i32 - 1, *height as i32 - 1))
+            }
+            Cells::Unbounded {
+                bounds: Some(bounds),
+                ..
+            } => *bounds,
+            Cells::Unbounded { bounds: None, .. } => return Ok(()),
+        };
+        let clip = |start: i32, length: i32, min: i32, max: i32| {
+            let end = i64::from(start) + i64::from(length) - 1;
+            (start.max(min), end.min(i64::from(max)) as i32)
+        };
+        let (start_x, end_x) = clip(origin.0, size.0, min_x, max_x);
+        let (start_y, end_y) = clip(origin.1, size.1, min_y, max_y);
+        if start_x > end_x || start_y > end_y {
+            return Ok(());
+        }
+        let mut empty_lines = 0;
+        let mut buffer = [0; 4];
+        for y in start_y..=end_y {
+            let mut spaces = 0;
+            let mut empty = true;
+            for x in start_x..=end_x {
+                let value = self.get(x, y).unwrap_or(SPACE);
+                if text && value == SPACE {
+                    spaces += 1;
+                    continue;
+                }
+                if empty {
+                    for _ in 0..empty_lines {
+                        output.write_all(b"\n")?;
+                    }
+                    empty_lines = 0;
+                    empty = false;
+                }
+                for _ in 0..spaces {
+                    output.write_all(b" ")?;
+                }
+                spaces = 0;
+                let character =
+                    std::char::from_u32(value as u32).unwrap_or(char::REPLACEMENT_CHARACTER);
+                output.write_all(character.encode_utf8(&mut buffer).as_bytes())?;
+            }
+            if empty {
+                empty_lines += 1;
+            } else {
+                output.write_all(b"\n")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The number of cells from `min` to `max` included, saturating on
+/// platforms where it does not fit a `usize`.
+fn extent(min: i32, max: i32) -> usize {
+    usize::try_from(i64::from(max) - i64::from(min) + 1).unwrap_or(usize::MAX)
+}
+
+/// The range of steps `t` for which `position + t * delta` lies within
+/// `bounds`, or `None` if the path never enters them.
+fn crossing(
+    ((min_x, min_y), (max_x, max_y)): Bounds,
+    (x, y): (i32, i32),
+    (dx, dy): (i32, i32),
+) -> Option<(i64, i64)> {
+    let (first_x, last_x) = axis_crossing(min_x, max_x, x, dx)?;
+    let (first_y, last_y) = axis_crossing(min_y, max_y, y, dy)?;
+    let (first, last) = (first_x.max(first_y), last_x.min(last_y));
+    if first <= last {
+        Some((first, last))
+    } else {
+        None
+    }
+}
+
+fn axis_crossing(min: i32, max: i32, position: i32, delta: i32) -> Option<(i64, i64)> {
+    let (min, max, position, delta) = (
+        i64::from(min),
+        i64::from(max),
+        i64::from(position),
+        i64::from(delta),
+    );
+    let (first, last) = match delta {
+        0 if position >= min && position <= max => (i64::MIN, i64::MAX),
+        0 => return None,
+        _ if delta > 0 => (
+            ceil_div(min - position, delta),
+            floor_div(max - position, delta),
+        ),
+        _ => (
+            ceil_div(max - position, delta),
+            floor_div(min - position, delta),
+        ),
+    };
+    Some((first, last)).filter(|(first, last)| first <= last)
+}
+
+/// The number of steps `t` for which `position + t * delta` is `cell`, if
+/// `cell` lies on the path at all.
+fn steps_to((x, y): (i32, i32), (dx, dy): (i32, i32), cell: (i32, i32)) -> Option<i64> {
+    // The steps needed along one axis: `Some(None)` when any number will do.
+    let along = |position: i32, delta: i32, target: i32| {
+        let distance = i64::from(target) - i64::from(position);
+        match i64::from(delta) {
+            0 if distance == 0 => Some(None),
+            0 => None,
+            delta if distance % delta == 0 => Some(Some(distance / delta)),
+            _ => None,
+        }
+    };
+    match (along(x, dx, cell.0)?, along(y, dy, cell.1)?) {
+        (Some(steps_x), Some(steps_y)) if steps_x != steps_y => None,
+        (Some(steps), _) | (None, Some(steps)) => Some(steps),
+        (None, None) => Some(0),
+    }
+}
+
+/// The position `steps` deltas away from `position`, wrapping around the
+/// range of `i32` like repeated wrapping additions would.
+fn step((x, y): (i32, i32), (dx, dy): (i32, i32), steps: i64) -> (i32, i32) {
+    (
+        (i64::from(x) + steps * i64::from(dx)) as i32,
+        (i64::from(y) + steps * i64::from(dy)) as i32,
+    )
+}
+
+fn floor_div(numerator: i64, denominator: i64) -> i64 {
+    if denominator < 0 {
+        (-numerator).div_euclid(-denominator)
+    } else {
+        numerator.div_euclid(denominator)
+    }
+}
+
+fn ceil_div(numerator: i64, denominator: i64) -> i64 {
+    -floor_div(-numerator, denominator)
+}
+
+fn grow(bounds: Option<Bounds>, (x, y): (i32, i32)) -> Bounds {
+    match bounds {
+        None => ((x, y), (x, y)),
+        Some(((min_x, min_y), (max_x, max_y))) => {
+            ((min_x.min(x), min_y.min(y)), (max_x.max(x), max_y.max(y)))
+        }
+    }
+}
+
+impl fmt::Display for Playfield {
+    /// Renders the playfield as text, without trailing spaces or trailing
+    /// empty lines. Values that are not characters are shown as U+FFFD.
+    /// Unbounded playfields are rendered from the top left corner of their
+    /// bounding box.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (origin_x, origin_y) = match &self.cells {
+            Cells::Bounded { .. } => (0, 0),
+            Cells::Unbounded { bounds, .. } => bounds.map_or((0, 0), |(min, _)| min),
+        };
+        // Gaps are only filled between non-space cells, so sparse playfields
+        // cost as much as the text they render to.
+        let (mut row, mut column) = (i64::from(origin_y), i64::from(origin_x));
+        let mut written = false;
+        for (x, y, value) in self.iter().filter(|(_, _, value)| *value != SPACE) {
+            let (x, y) = (i64::from(x), i64::from(y));
+            if y != row {
+                for _ in row..y {
+                    f.write_str("\n")?;
+                }
+                row = y;
+                column = i64::from(origin_x);
+            }
+            for _ in column..x {
+                f.write_str(" ")?;
+            }
+            let character =
+                std::char::from_u32(value as u32).unwrap_or(char::REPLACEMENT_CHARACTER);
+            write!(f, "{}", character)?;
+            column = x + 1;
+            written = true;
+        }
+        if written {
+            f.write_str("\n")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(source: &str) -> Playfield {
+        let mut playfield = Playfield::default();
+        playfield.load(source.as_bytes(), LoadMode::Strict).unwrap();
+        playfield
+    }
+
+    #[test]
+    fn default() {
+        let playfield = Playfield::default();
+        assert_eq!(80, playfield.width());
+        assert_eq!(25, playfield.height());
+        assert!(playfield.iter().all(|(_, _, value)| value == SPACE));
+    }
+
+    #[test]
+    #[should_panic]
+    fn new_empty() {
+        Playfield::new(0, 25);
+    }
+
+    #[test]
+    fn load_coordinates() {
+        let playfield = load("ab\nc");
+        assert_eq!(Some('a' as i32), playfield.get(0, 0));
+        assert_eq!(Some('b' as i32), playfield.get(1, 0));
+        assert_eq!(Some('c' as i32), playfield.get(0, 1));
+    }
+
+    #[test]
+    fn load_outside() {
+        let mut playfield = Playfield::new(2, 2);
+        match playfield.load("abc".as_bytes(), LoadMode::Strict) {
+            Err(LoadError::OutsidePlayfield { line, column }) => assert_eq!((1, 3), (line, column)),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn load_truncate() {
+        let mut playfield = Playfield::new(2, 2);
+        let warnings = playfield
+            .load("abc\nd\ne".as_bytes(), LoadMode::Truncate)
+            .unwrap();
+        assert_eq!(
+            vec![
+                Warning::Truncated { line: 1, column: 3 },
+                Warning::Truncated { line: 3, column: 1 }
+            ],
+            warnings
+        );
+        assert_eq!(Some('b' as i32), playfield.get(1, 0));
+        assert_eq!(Some('d' as i32), playfield.get(0, 1));
+    }
+
+    #[test]
+    fn in_bounds() {
+        let playfield = Playfield::default();
+        assert!(playfield.in_bounds(0, 0));
+        assert!(playfield.in_bounds(79, 24));
+        assert!(!playfield.in_bounds(80, 0));
+        assert!(!playfield.in_bounds(0, 25));
+        assert!(!playfield.in_bounds(-1, 0));
+        assert!(!playfield.in_bounds(0, -1));
+    }
+
+    #[test]
+    fn wrap() {
+        let playfield = Playfield::default();
+        assert_eq!((0, 0), playfield.wrap(80, 25));
+        assert_eq!((79, 24), playfield.wrap(-1, -1));
+        assert_eq!((5, 3), playfield.wrap(5, 3));
+    }
+
+    #[test]
+    fn get_out_of_bounds() {
+        let playfield = Playfield::default();
+        assert_eq!(None, playfield.get(80, 0));
+        assert_eq!(None, playfield.get(-1, 0));
+    }
+
+    #[test]
+    fn set() {
+        let mut playfield = Playfield::default();
+        assert!(playfield.set(3, 4, 300));
+        assert_eq!(Some(300), playfield.get(3, 4));
+        assert!(!playfield.set(80, 4, 300));
+    }
+
+    #[test]
+    fn iter() {
+        let playfield = Playfield::new(2, 2);
+        let coordinates: Vec<(i32, i32)> = playfield.iter().map(|(x, y, _)| (x, y)).collect();
+        assert_eq!(vec![(0, 0), (1, 0), (0, 1), (1, 1)], coordinates);
+    }
+
+    #[test]
+    fn iter_unbounded() {
+        let mut playfield = Playfield::unbounded();
+        playfield.set(5, 1, 'c' as i32);
+        playfield.set(-3, 1, 'b' as i32);
+        playfield.set(i32::MAX, -7, 'a' as i32);
+        playfield.set(0, 0, SPACE);
+        let cells: Vec<(i32, i32, i32)> = playfield.iter().collect();
+        assert_eq!(
+            vec![
+                (i32::MAX, -7, 'a' as i32),
+                (-3, 1, 'b' as i32),
+                (5, 1, 'c' as i32)
+            ],
+            cells
+        );
+    }
+
+    #[test]
+    fn bounding_box() {
+        let playfield = load("\n  a\n b  c");
+        assert_eq!(Some(((1, 1), (4, 2))), playfield.bounding_box());
+    }
+
+    #[test]
+    fn bounding_box_empty() {
+        assert_eq!(None, Playfield::default().bounding_box());
+    }
+
+    #[test]
+    fn display() {
+        let mut playfield = load("ab  \n\nc");
+        playfield.set(5, 0, -1);
+        assert_eq!("ab   \u{FFFD}\n\nc\n", playfield.to_string());
+    }
+
+    #[test]
+    fn unbounded_empty() {
+        let playfield = Playfield::unbounded();
+        assert!(playfield.is_unbounded());
+        assert_eq!(0, playfield.width());
+        assert_eq!(0, playfield.height());
+        assert_eq!(None, playfield.bounding_box());
+        assert_eq!(Some(SPACE), playfield.get(-1000, 1000));
+        assert_eq!("", playfield.to_string());
+    }
+
+    #[test]
+    fn unbounded_load() {
+        let mut playfield = Playfield::unbounded();
+        let line = "a".repeat(200);
+        let warnings = playfield.load(line.as_bytes(), LoadMode::Strict).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(Some('a' as i32), playfield.get(199, 0));
+        assert_eq!(Some(((0, 0), (199, 0))), playfield.bounding_box());
+    }
+
+    #[test]
+    fn unbounded_set_grows() {
+        let mut playfield = Playfield::unbounded();
+        assert!(playfield.in_bounds(-5, -3));
+        assert!(playfield.set(-5, -3, 'x' as i32));
+        assert!(playfield.set(2, 1, 'y' as i32));
+        assert_eq!(Some('x' as i32), playfield.get(-5, -3));
+        assert_eq!(Some(((-5, -3), (2, 1))), playfield.bounding_box());
+        assert_eq!((8, 5), (playfield.width(), playfield.height()));
+    }
+
+    #[test]
+    fn unbounded_set_space() {
+        let mut playfield = Playfield::unbounded();
+        playfield.set(3, 3, 'x' as i32);
+        playfield.set(3, 3, SPACE);
+        assert_eq!(Some(SPACE), playfield.get(3, 3));
+        assert_eq!(Some(((3, 3), (3, 3))), playfield.bounding_box());
+    }
+
+    #[test]
+    fn unbounded_wrap() {
+        let playfield = Playfield::unbounded();
+        assert_eq!((-100, 300), playfield.wrap(-100, 300));
+    }
+
+    #[test]
+    fn unbounded_display() {
+        let mut playfield = Playfield::unbounded();
+        playfield.set(-1, -1, 'a' as i32);
+        playfield.set(1, 0, 'b' as i32);
+        assert_eq!("a\n  b\n", playfield.to_string());
+    }
+
+    #[test]
+    fn unbounded_display_far_apart() {
+        let mut playfield = Playfield::unbounded();
+        playfield.set(0, 0, 'a' as i32);
+        playfield.set(100_000, 100_000, 'b' as i32);
+        let text = playfield.to_string();
+        assert_eq!(200_003, text.len());
+        assert!(text.starts_with("a\n\n"));
+        assert!(text.ends_with(" b\n"));
+    }
+
+    #[test]
+    fn advance_bounded() {
+        let playfield = Playfield::new(3, 2);
+        assert_eq!((1, 0), playfield.advance((0, 0), (1, 0)));
+        assert_eq!((0, 0), playfield.advance((2, 0), (1, 0)));
+        assert_eq!((0, 1), playfield.advance((0, 0), (0, -1)));
+    }
+
+    #[test]
+    fn advance_lahey() {
+        let mut playfield = Playfield::unbounded();
+        playfield
+            .load("abc\nd  e".as_bytes(), LoadMode::Strict)
+            .unwrap();
+        assert_eq!((1, 0), playfield.advance((0, 0), (1, 0)));
+        assert_eq!((0, 0), playfield.advance((3, 0), (1, 0)));
+        assert_eq!((3, 1), playfield.advance((0, 1), (-1, 0)));
+        assert_eq!((2, 0), playfield.advance((2, 1), (0, 1)));
+    }
+
+    #[test]
+    fn advance_lahey_diagonal() {
+        let mut playfield = Playfield::unbounded();
+        playfield.set(0, 0, 'a' as i32);
+        playfield.set(4, 4, 'b' as i32);
+        assert_eq!((0, 1), playfield.advance((3, 4), (1, 1)));
+    }
+
+    #[test]
+    fn advance_lahey_outside() {
+        let mut playfield = Playfield::unbounded();
+        assert_eq!((-7, 2), playfield.advance((-8, 2), (1, 0)));
+        playfield.set(0, 0, 'a' as i32);
+        assert_eq!((-7, 2), playfield.advance((-8, 2), (1, 0)));
+    }
+
+    #[test]
+    fn advance_lahey_back_into_box() {
+        let mut playfield = Playfield::unbounded();
+        playfield.load("abc".as_bytes(), LoadMode::Strict).unwrap();
+        assert_eq!((0, 0), playfield.advance((10, 0), (1, 0)));
+        assert_eq!((2, 0), playfield.advance((-10, 0), (-1, 0)));
+        assert_eq!((0, 0), playfield.advance((-10, 0), (1, 0)));
+        assert_eq!((2, 0), playfield.advance((5, 3), (1, 1)));
+        assert_eq!((11, 1), playfield.advance((10, 0), (1, 1)));
+    }
+
+    #[test]
+    fn advance_lahey_far_apart() {
+        let mut playfield = Playfield::unbounded();
+        playfield.set(-1 << 30, 0, 'a' as i32);
+        playfield.set(1 << 30, 0, 'b' as i32);
+        assert_eq!((-1 << 30, 0), playfield.advance((1 << 30, 0), (1, 0)));
+        assert_eq!((-(1 << 30) + 2, 0), playfield.advance((1 << 30, 0), (3, 0)));
+        assert_eq!((i32::MIN, 0), playfield.advance((i32::MIN, 0), (0, 0)));
+        assert_eq!((1 << 31) + 1, playfield.width());
+    }
+
+    #[test]
+    fn unbounded_extremes() {
+        let mut playfield = Playfield::unbounded();
+        playfield.set(i32::MIN, i32::MIN, 'a' as i32);
+        playfield.set(i32::MAX, i32::MAX, 'b' as i32);
+        assert_eq!(1 << 32, playfield.width());
+        assert_eq!(1 << 32, playfield.height());
+        assert_eq!(
+            (i32::MIN, i32::MIN),
+            playfield.advance((i32::MAX, i32::MAX), (1, 1))
+        );
+    }
+
+    #[test]
+    fn advance_by() {
+        let playfield = Playfield::new(3, 2);
+        assert_eq!((1, 0), playfield.advance_by((0, 0), (1, 0), 4));
+        assert_eq!((0, 1), playfield.advance_by((0, 0), (-1, 1), u32::MAX));
+        let mut playfield = Playfield::unbounded();
+        playfield
+            .load("abcde".as_bytes(), LoadMode::Strict)
+            .unwrap();
+        assert_eq!((3, 0), playfield.advance_by((1, 0), (1, 0), 2));
+        assert_eq!((0, 0), playfield.advance_by((1, 0), (1, 0), 4));
+        assert_eq!((2, 0), playfield.advance_by((1, 0), (-1, 0), 9));
+        assert_eq!((0, 0), playfield.advance_by((-10, 0), (1, 0), 1));
+        assert_eq!((1, 0), playfield.advance_by((-10, 0), (1, 0), 7));
+        assert_eq!((2, 4), playfield.advance_by((0, 2), (1, 1), 2));
+        assert_eq!((2, 0), playfield.advance_by((2, 0), (0, 0), 5));
+        for steps in 0..12 {
+            let walked = (0..steps).fold((1, 0), |position, _| playfield.advance(position, (2, 0)));
+            assert_eq!(walked, playfield.advance_by((1, 0), (2, 0), steps));
+        }
+    }
+
+    #[test]
+    fn skip_spaces() {
+        let mut playfield = Playfield::unbounded();
+        playfield
+            .load("a  b\n   c".as_bytes(), LoadMode::Strict)
+            .unwrap();
+        assert_eq!(Some((3, 0)), playfield.skip_spaces((0, 0), (1, 0)));
+        assert_eq!(Some((0, 0)), playfield.skip_spaces((3, 0), (1, 0)));
+        assert_eq!(Some((3, 1)), playfield.skip_spaces((0, 1), (1, 0)));
+        assert_eq!(Some((3, 0)), playfield.skip_spaces((3, 1), (0, 1)));
+        assert_eq!(None, playfield.skip_spaces((1, 0), (0, 1)));
+        assert_eq!(None, playfield.skip_spaces((0, 5), (1, 0)));
+        assert_eq!(Some((0, 5)), playfield.skip_spaces((0, 5), (0, 0)));
+        assert_eq!(3, playfield.occupied());
+    }
+
+    #[test]
+    fn skip_spaces_bounded() {
+        let playfield = Playfield::new(3, 2);
+        assert_eq!(Some((1, 0)), playfield.skip_spaces((0, 0), (1, 0)));
+        assert_eq!(0, playfield.occupied());
+    }
+
+    #[test]
+    fn load_at_text() {
+        let mut playfield = Playfield::unbounded();
+        playfield.set(3, 5, 'z' as i32);
+        let size = playfield.load_at((1, 5), "ab c\r\nde\n".as_bytes(), false);
+        assert_eq!((4, 2), size);
+        assert_eq!(Some('a' as i32), playfield.get(1, 5));
+        assert_eq!(Some('z' as i32), playfield.get(3, 5));
+        assert_eq!(Some('c' as i32), playfield.get(4, 5));
+        assert_eq!(Some('e' as i32), playfield.get(2, 6));
+    }
+
+    #[test]
+    fn load_at_binary() {
+        let mut playfield = Playfield::new(4, 2);
+        let size = playfield.load_at((1, 1), b"a\nbc", true);
+        assert_eq!((4, 1), size);
+        assert_eq!(Some('\n' as i32), playfield.get(2, 1));
+        assert_eq!(Some('b' as i32), playfield.get(3, 1));
+    }
+
+    fn region(playfield: &Playfield, origin: (i32, i32), size: (i32, i32), text: bool) -> String {
+        let mut output = vec![];
+        playfield
+            .write_region(origin, size, text, &mut output)
+            .unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn write_region() {
+        let playfield = load("abc \n d\n\n");
+        assert_eq!(
+            "abc \n d  \n    \n",
+            region(&playfield, (0, 0), (4, 3), false)
+        );
+        assert_eq!("abc\n d\n", region(&playfield, (0, 0), (4, 3), true));
+        assert_eq!("bc\n", region(&playfield, (1, 0), (2, 1), true));
+        assert_eq!("", region(&playfield, (0, 0), (-1, 3), false));
+        assert_eq!("  \n", region(&playfield, (78, 24), (10, 10), false));
+    }
+
+    #[test]
+    fn write_region_unbounded() {
+        let mut playfield = Playfield::unbounded();
+        playfield
+            .load("a\n\n  b".as_bytes(), LoadMode::Strict)
+            .unwrap();
+        assert_eq!("a\n\n  b\n", region(&playfield, (0, 0), (10, 10), true));
+        assert_eq!(
+            "a  \n   \n  b\n",
+            region(&playfield, (0, 0), (10, 10), false)
+        );
+        assert_eq!(
+            "a\n\n  b\n",
+            region(&playfield, (-5, -5), (i32::MAX, i32::MAX), true)
+        );
+        assert_eq!("", region(&Playfield::unbounded(), (0, 0), (5, 5), false));
+    }
+}
@@ -1,3 +1,6 @@
+use crate::playfield::Playfield;
+
+/// The cardinal directions the pointer can travel in.
 #[derive(PartialEq, Debug, Copy, Clone)]
 pub enum Direction {
     Up,
@@ -6,60 +9,112 @@ pub enum Direction {
     Right,
 }
 
-#[derive(Debug)]
+impl Direction {
+    pub fn reverse(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+
+    /// The `(dx, dy)` moved by one step in this direction.
+    pub fn delta(self) -> (i32, i32) {
+        match self {
+            Direction::Right => (1, 0),
+            Direction::Left => (-1, 0),
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+        }
+    }
+
+    /// The direction moving by `delta`, if it is a single cardinal step.
+    pub fn from_delta(delta: (i32, i32)) -> Option<Direction> {
+        match delta {
+            (1, 0) => Some(Direction::Right),
+            (-1, 0) => Some(Direction::Left),
+            (0, -1) => Some(Direction::Up),
+            (0, 1) => Some(Direction::Down),
+            _ => None,
+        }
+    }
+}
+
+/// The instruction pointer. Coordinates are expressed as `(x, y)` in the
+/// same system as the [`Playfield`], and the pointer moves by its delta
+/// `(dx, dy)` at every step.
+#[derive(Debug, Clone)]
 pub struct Pointer {
-    direction: Direction,
+    delta: (i32, i32),
     x: i32,
     y: i32,
-    max_rows: i32,
-    max_cols: i32,
 }
 
 impl Default for Pointer {
     fn default() -> Self {
         Pointer {
-            direction: Direction::Right,
+            delta: Direction::Right.delta(),
             x: 0,
             y: 0,
-            max_rows: 25,
-            max_cols: 80,
         }
     }
 }
 
 impl Pointer {
-    pub fn increase(&mut self) {
-        match self.direction {
-            Direction::Right => self.y = (self.y + 1) % self.max_cols,
-            Direction::Left => {
-                if self.y == 0 {
-                    self.y = self.max_cols - 1;
-                } else {
-                    self.y = 0.max(self.y - 1);
-                }
-            }
-            Direction::Up => {
-                if self.x == 0 {
-                    self.x = self.max_rows - 1;
-                } else {
-                    self.x = 0.max(self.x - 1);
-                }
-            }
-            Direction::Down => self.x = (self.x + 1) % self.max_rows,
-        }
+    /// Moves by the current delta, wrapping around the edges of `playfield`
+    /// as described by [`Playfield::advance`].
+    pub fn increase(&mut self, playfield: &Playfield) {
+        let (x, y) = playfield.advance((self.x, self.y), self.delta);
+        self.x = x;
+        self.y = y;
     }
 
     pub fn set_direction(&mut self, direction: Direction) {
-        self.direction = direction;
+        self.delta = direction.delta();
+    }
+
+    /// Sets an arbitrary delta, such as the flying deltas of Funge-98.
+    pub fn set_delta(&mut self, delta: (i32, i32)) {
+        self.delta = delta;
+    }
+
+    /// Reverses the delta.
+    pub fn reflect(&mut self) {
+        let (dx, dy) = self.delta;
+        self.delta = (dx.wrapping_neg(), dy.wrapping_neg());
+    }
+
+    /// Rotates the delta by 90 degrees counterclockwise, as seen on screen.
+    pub fn turn_left(&mut self) {
+        let (dx, dy) = self.delta;
+        self.delta = (dy, dx.wrapping_neg());
+    }
+
+    /// Rotates the delta by 90 degrees clockwise, as seen on screen.
+    pub fn turn_right(&mut self) {
+        let (dx, dy) = self.delta;
+        self.delta = (dy.wrapping_neg(), dx);
     }
 
     pub fn coordinates(&self) -> (i32, i32) {
         (self.x, self.y)
     }
 
-    #[cfg(test)]
-    pub fn direction(&self) -> Direction {
-        self.direction
+    /// Moves straight to `(x, y)`, keeping the delta.
+    pub fn set_coordinates(&mut self, x: i32, y: i32) {
+        self.x = x;
+        self.y = y;
+    }
+
+    pub fn delta(&self) -> (i32, i32) {
+        self.delta
+    }
+
+    /// The cardinal direction of the pointer, or `None` when the delta is
+    /// not a single cardinal step.
+    pub fn direction(&self) -> Option<Direction> {
+        Direction::from_delta(self.delta)
     }
 }
 
@@ -71,14 +126,16 @@ mod tests {
         Pointer::default()
     }
 
+    fn playfield() -> Playfield {
+        Playfield::default()
+    }
+
     #[test]
     fn default() {
         let pointer = Pointer::default();
         assert_eq!(0, pointer.x);
         assert_eq!(0, pointer.y);
-        assert_eq!(25, pointer.max_rows);
-        assert_eq!(80, pointer.max_cols);
-        assert_eq!(Direction::Right, pointer.direction);
+        assert_eq!((1, 0), pointer.delta);
     }
     #[test]
     fn coordinates() {
@@ -90,31 +147,122 @@ mod tests {
     fn set_direction() {
         let mut pointer = new_pointer();
         pointer.set_direction(Direction::Down);
-        assert_eq!(Direction::Down, pointer.direction);
+        assert_eq!((0, 1), pointer.delta);
+    }
+
+    #[test]
+    fn set_coordinates() {
+        let mut pointer = new_pointer();
+        pointer.set_coordinates(-4, 9);
+        assert_eq!((-4, 9), pointer.coordinates());
+        assert_eq!(Some(Direction::Right), pointer.direction());
+    }
+
+    #[test]
+    fn set_delta() {
+        let mut pointer = new_pointer();
+        pointer.set_delta((2, -3));
+        assert_eq!((2, -3), pointer.delta());
+        assert_eq!(None, pointer.direction());
+    }
+
+    #[test]
+    fn reverse() {
+        assert_eq!(Direction::Down, Direction::Up.reverse());
+        assert_eq!(Direction::Up, Direction::Down.reverse());
+        assert_eq!(Direction::Right, Direction::Left.reverse());
+        assert_eq!(Direction::Left, Direction::Right.reverse());
+    }
+
+    #[test]
+    fn from_delta() {
+        for direction in &[
+            Direction::Up,
+            Direction::Down,
+            Direction::Left,
+            Direction::Right,
+        ] {
+            assert_eq!(Some(*direction), Direction::from_delta(direction.delta()));
+        }
+        assert_eq!(None, Direction::from_delta((1, 1)));
+        assert_eq!(None, Direction::from_delta((0, 0)));
+    }
+
+    #[test]
+    fn reflect() {
+        let mut pointer = new_pointer();
+        pointer.reflect();
+        assert_eq!(Some(Direction::Left), pointer.direction());
+        pointer.set_delta((2, -3));
+        pointer.reflect();
+        assert_eq!((-2, 3), pointer.delta());
+    }
+
+    #[test]
+    fn turn_left() {
+        let mut pointer = new_pointer();
+        let mut seen = vec![];
+        for _ in 0..4 {
+            pointer.turn_left();
+            seen.push(pointer.direction().unwrap());
+        }
+        assert_eq!(
+            vec![
+                Direction::Up,
+                Direction::Left,
+                Direction::Down,
+                Direction::Right
+            ],
+            seen
+        );
+    }
+
+    #[test]
+    fn turn_right() {
+        let mut pointer = new_pointer();
+        pointer.turn_right();
+        assert_eq!(Some(Direction::Down), pointer.direction());
+        pointer.set_delta((2, 1));
+        pointer.turn_right();
+        assert_eq!((-1, 2), pointer.delta());
+        pointer.turn_left();
+        assert_eq!((2, 1), pointer.delta());
+    }
+
+    #[test]
+    fn increase_delta() {
+        let mut pointer = new_pointer();
+        pointer.set_delta((3, 2));
+        pointer.increase(&playfield());
+        pointer.increase(&playfield());
+        assert_eq!((6, 4), pointer.coordinates());
+        pointer.set_delta((-7, 0));
+        pointer.increase(&playfield());
+        assert_eq!((79, 4), pointer.coordinates());
     }
 
     #[test]
     fn increase_right() {
         let mut pointer = new_pointer();
-        pointer.increase();
-        assert_eq!((0, 1), pointer.coordinates());
+        pointer.increase(&playfield());
+        assert_eq!((1, 0), pointer.coordinates());
     }
 
     #[test]
     fn increase_down() {
         let mut pointer = new_pointer();
         pointer.set_direction(Direction::Down);
-        pointer.increase();
-        assert_eq!((1, 0), pointer.coordinates());
+        pointer.increase(&playfield());
+        assert_eq!((0, 1), pointer.coordinates());
     }
 
     #[test]
     fn increase_left() {
         let mut pointer = new_pointer();
-        pointer.increase();
-        assert_eq!((0, 1), pointer.coordinates());
+        pointer.increase(&playfield());
+        assert_eq!((1, 0), pointer.coordinates());
         pointer.set_direction(Direction::Left);
-        pointer.increase();
+        pointer.increase(&playfield());
         assert_eq!((0, 0), pointer.coordinates());
     }
 
@@ -122,21 +270,22 @@ mod tests {
     fn increase_up() {
         let mut pointer = new_pointer();
         pointer.set_direction(Direction::Down);
-        pointer.increase();
-        assert_eq!((1, 0), pointer.coordinates());
+        pointer.increase(&playfield());
+        assert_eq!((0, 1), pointer.coordinates());
         pointer.set_direction(Direction::Up);
-        pointer.increase();
+        pointer.increase(&playfield());
         assert_eq!((0, 0), pointer.coordinates());
     }
 
     #[test]
     fn increase_right_wrap() {
         let mut pointer = new_pointer();
-        for _ in 1..pointer.max_cols {
-            pointer.increase();
+        let playfield = playfield();
+        for _ in 1..playfield.width() {
+            pointer.increase(&playfield);
         }
-        assert_eq!((0, 79), pointer.coordinates());
-        pointer.increase();
+        assert_eq!((79, 0), pointer.coordinates());
+        pointer.increase(&playfield);
         assert_eq!((0, 0), pointer.coordinates());
     }
 
@@ -144,33 +293,60 @@ mod tests {
     fn increase_left_wrap() {
         let mut pointer = new_pointer();
         pointer.set_direction(Direction::Left);
-        pointer.increase();
-        assert_eq!((0, 79), pointer.coordinates());
+        pointer.increase(&playfield());
+        assert_eq!((79, 0), pointer.coordinates());
     }
 
     #[test]
     fn increase_up_wrap() {
         let mut pointer = new_pointer();
         pointer.set_direction(Direction::Up);
-        pointer.increase();
-        assert_eq!((24, 0), pointer.coordinates());
+        pointer.increase(&playfield());
+        assert_eq!((0, 24), pointer.coordinates());
     }
 
     #[test]
     fn increase_down_wrap() {
         let mut pointer = new_pointer();
         pointer.set_direction(Direction::Down);
-        for _ in 1..pointer.max_rows {
-            pointer.increase();
+        let playfield = playfield();
+        for _ in 1..playfield.height() {
+            pointer.increase(&playfield);
         }
-        assert_eq!((24, 0), pointer.coordinates());
-        pointer.increase();
+        assert_eq!((0, 24), pointer.coordinates());
+        pointer.increase(&playfield);
+        assert_eq!((0, 0), pointer.coordinates());
+    }
+
+    #[test]
+    fn increase_wrap_small_playfield() {
+        let mut pointer = new_pointer();
+        let playfield = Playfield::new(3, 2);
+        for _ in 0..3 {
+            pointer.increase(&playfield);
+        }
+        assert_eq!((0, 0), pointer.coordinates());
+        pointer.set_direction(Direction::Up);
+        pointer.increase(&playfield);
+        assert_eq!((0, 1), pointer.coordinates());
+    }
+
+    #[test]
+    fn increase_unbounded_wrap() {
+        let mut pointer = new_pointer();
+        let mut playfield = Playfield::unbounded();
+        playfield.set(0, 0, '>' as i32);
+        playfield.set(2, 0, '<' as i32);
+        pointer.increase(&playfield);
+        pointer.increase(&playfield);
+        assert_eq!((2, 0), pointer.coordinates());
+        pointer.increase(&playfield);
         assert_eq!((0, 0), pointer.coordinates());
     }
 
     #[test]
     fn direction() {
         let pointer = new_pointer();
-        assert_eq!(Direction::Right, pointer.direction);
+        assert_eq!(Some(Direction::Right), pointer.direction());
     }
 }
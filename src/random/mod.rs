@@ -0,0 +1,98 @@
+use crate::pointer::Direction;
+use rand::{rngs::StdRng, FromEntropy, Rng, SeedableRng};
+use std::fmt::Debug;
+
+/// Chooses the direction taken by the `?` instruction.
+pub trait DirectionSource: Debug {
+    fn next_direction(&mut self) -> Direction;
+}
+
+/// Picks directions uniformly at random.
+#[derive(Debug)]
+pub struct RandomDirections {
+    rng: StdRng,
+}
+
+impl Default for RandomDirections {
+    fn default() -> Self {
+        RandomDirections {
+            rng: StdRng::from_entropy(),
+        }
+    }
+}
+
+impl RandomDirections {
+    /// Creates a source that yields the same sequence of directions for the same seed.
+    pub fn seeded(seed: u64) -> Self {
+        RandomDirections {
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl DirectionSource for RandomDirections {
+    fn next_direction(&mut self) -> Direction {
+        match self.rng.gen_range(0, 4) {
+            0 => Direction::Right,
+            1 => Direction::Left,
+            2 => Direction::Up,
+            _ => Direction::Down,
+        }
+    }
+}
+
+/// Replays a fixed sequence of directions, starting over once exhausted.
+#[derive(Debug)]
+pub struct ScriptedDirections {
+    directions: Vec<Direction>,
+    next: usize,
+}
+
+impl ScriptedDirections {
+    pub fn new(directions: Vec<Direction>) -> Self {
+        assert!(
+            !directions.is_empty(),
+            "Scripted directions cannot be empty"
+        );
+        ScriptedDirections {
+            directions,
+            next: 0,
+        }
+    }
+}
+
+impl DirectionSource for ScriptedDirections {
+    fn next_direction(&mut self) -> Direction {
+        let direction = self.directions[self.next];
+        self.next = (self.next + 1) % self.directions.len();
+        direction
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeded_is_reproducible() {
+        let mut first = RandomDirections::seeded(42);
+        let mut second = RandomDirections::seeded(42);
+        for _ in 0..100 {
+            assert_eq!(first.next_direction(), second.next_direction());
+        }
+    }
+
+    #[test]
+    fn scripted_replays_in_order() {
+        let mut directions = ScriptedDirections::new(vec![Direction::Up, Direction::Left]);
+        assert_eq!(Direction::Up, directions.next_direction());
+        assert_eq!(Direction::Left, directions.next_direction());
+        assert_eq!(Direction::Up, directions.next_direction());
+    }
+
+    #[test]
+    #[should_panic]
+    fn scripted_empty() {
+        ScriptedDirections::new(vec![]);
+    }
+}
@@ -0,0 +1,158 @@
+use std::fs::{self, File};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// Confines the files read by `i` and written by `o` to a root directory.
+#[derive(Debug, Clone)]
+pub struct Sandbox {
+    root: PathBuf,
+}
+
+impl Sandbox {
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Sandbox { root: root.into() }
+    }
+
+    /// Resolves a path given by the program against the root. Returns `None`
+    /// for absolute paths and for paths leaving the root, whether through
+    /// `..` or through symbolic links.
+    pub fn resolve(&self, path: &str) -> Option<PathBuf> {
+        let relative = Path::new(path);
+        let mut components = relative.components().peekable();
+        components.peek()?;
+        if !components
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+        {
+            return None;
+        }
+        let root = self.root.canonicalize().ok()?;
+        let resolved = root.join(relative);
+        let parent = resolved.parent()?.canonicalize().ok()?;
+        let target = match resolved.canonicalize() {
+            Ok(target) => target,
+            // A dangling symbolic link could point anywhere once written.
+            Err(_) if fs::symlink_metadata(&resolved).is_ok() => return None,
+            Err(_) => parent.join(resolved.file_name()?),
+        };
+        if parent.starts_with(&root) && target.starts_with(&root) {
+            Some(target)
+        } else {
+            None
+        }
+    }
+
+    pub fn read(&self, path: &str) -> io::Result<Vec<u8>> {
+        fs::read(self.resolve(path).ok_or_else(outside)?)
+    }
+
+    /// Creates or truncates a file for writing, so large contents can be
+    /// streamed to it.
+    pub fn create(&self, path: &str) -> io::Result<File> {
+        File::create(self.resolve(path).ok_or_else(outside)?)
+    }
+}
+
+fn outside() -> io::Error {
+    io::Error::new(io::ErrorKind::PermissionDenied, "Path outside the sandbox")
+}
+
+#[cfg(test)]
+pub(crate) mod test_support {
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::process;
+
+    /// A directory under the system temporary directory, removed with its
+    /// contents when dropped.
+    pub(crate) struct TempDir(PathBuf);
+
+    impl TempDir {
+        pub(crate) fn new(name: &str) -> Self {
+            let path = env::temp_dir().join(format!("bfng-{}-{}", name, process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            TempDir(path)
+        }
+
+        pub(crate) fn path(&self) -> &Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_support::TempDir;
+    use super::*;
+    use std::env;
+    use std::io::Write;
+    use std::process;
+
+    fn sandbox(name: &str) -> (Sandbox, TempDir) {
+        let root = TempDir::new(&format!("sandbox-{}", name));
+        fs::create_dir_all(root.path().join("data")).unwrap();
+        (Sandbox::new(root.path()), root)
+    }
+
+    #[test]
+    fn resolve_inside() {
+        let (sandbox, _root) = sandbox("inside");
+        let root = sandbox.root.canonicalize().unwrap();
+        assert_eq!(Some(root.join("a.txt")), sandbox.resolve("a.txt"));
+        assert_eq!(
+            Some(root.join("data/b.txt")),
+            sandbox.resolve("./data/b.txt")
+        );
+    }
+
+    #[test]
+    fn resolve_outside() {
+        let (sandbox, _root) = sandbox("outside");
+        assert_eq!(None, sandbox.resolve(""));
+        assert_eq!(None, sandbox.resolve("/etc/passwd"));
+        assert_eq!(None, sandbox.resolve("../a.txt"));
+        assert_eq!(None, sandbox.resolve("data/../../a.txt"));
+        assert_eq!(None, sandbox.resolve("missing/a.txt"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn resolve_symlink_outside() {
+        let (sandbox, _root) = sandbox("symlink");
+        let link = sandbox.root.join("escape");
+        let _ = fs::remove_file(&link);
+        std::os::unix::fs::symlink(env::temp_dir(), &link).unwrap();
+        assert_eq!(None, sandbox.resolve("escape/a.txt"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn resolve_dangling_symlink() {
+        let (sandbox, _root) = sandbox("dangling");
+        let outside = env::temp_dir().join(format!("bfng-dangling-{}", process::id()));
+        let _ = fs::remove_file(&outside);
+        let link = sandbox.root.join("link");
+        let _ = fs::remove_file(&link);
+        std::os::unix::fs::symlink(&outside, &link).unwrap();
+        assert_eq!(None, sandbox.resolve("link"));
+        assert!(sandbox.create("link").is_err());
+        assert!(!outside.exists());
+    }
+
+    #[test]
+    fn create_read() {
+        let (sandbox, _root) = sandbox("write-read");
+        let mut file = sandbox.create("data/c.txt").unwrap();
+        file.write_all(b"abc").unwrap();
+        assert_eq!(b"abc".to_vec(), sandbox.read("data/c.txt").unwrap());
+        let error = sandbox.create("../c.txt").unwrap_err();
+        assert_eq!(io::ErrorKind::PermissionDenied, error.kind());
+    }
+}
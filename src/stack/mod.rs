@@ -1,37 +1,325 @@
+use crate::error::InterpreterError;
 use std::convert::TryInto;
+use std::ops::{Deref, DerefMut};
 
-#[derive(Debug)]
+/// The Befunge stack. Popping an empty stack yields zero.
+///
+/// Runs of zeros pushed by the stack stack instructions are kept as a count,
+/// so a block of any size costs no more than the cells actually moved.
+#[derive(Debug, Clone)]
 pub struct Stack {
-    stack: Vec<i32>,
+    /// From bottom to top. The first segment is always a (possibly empty)
+    /// run of cells and no other segment is empty.
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Cells(Vec<i32>),
+    Zeros(usize),
 }
 
 impl Default for Stack {
     fn default() -> Self {
-        Stack { stack: vec![] }
+        Stack {
+            segments: vec![Segment::Cells(vec![])],
+        }
     }
 }
 
 impl Stack {
     pub fn pop(&mut self) -> i32 {
-        let pop_result = self.stack.pop();
-        if let Some(value) = pop_result {
-            value
-        } else {
-            0
+        let single = self.segments.len() == 1;
+        let (value, emptied) = match self.segments.last_mut() {
+            Some(Segment::Cells(cells)) => (cells.pop().unwrap_or_default(), cells.is_empty()),
+            Some(Segment::Zeros(count)) => {
+                *count -= 1;
+                (0, *count == 0)
+            }
+            None => (0, false),
+        };
+        if emptied && !single {
+            self.segments.pop();
         }
+        value
     }
 
     pub fn push(&mut self, value: i32) {
-        self.stack.push(value);
+        match self.segments.last_mut() {
+            Some(Segment::Cells(cells)) => cells.push(value),
+            _ => self.segments.push(Segment::Cells(vec![value])),
+        }
     }
 
-    pub fn pop_char(&mut self) -> char {
-        std::char::from_u32(self.pop().try_into().unwrap()).unwrap()
+    pub fn pop_char(&mut self) -> Result<char, InterpreterError> {
+        let value = self.pop();
+        value
+            .try_into()
+            .ok()
+            .and_then(std::char::from_u32)
+            .ok_or(InterpreterError::InvalidCharacter(value))
+    }
+
+    /// Pops characters up to the first zero, as left by `"` with a string
+    /// written in reverse. The whole string is popped even when one of its
+    /// values is not a character, which is then reported.
+    pub fn pop_string(&mut self) -> Result<String, InterpreterError> {
+        let mut string = Ok(String::new());
+        loop {
+            match self.pop_char() {
+                Ok('\0') => return string,
+                Ok(character) => {
+                    if let Ok(string) = &mut string {
+                        string.push(character);
+                    }
+                }
+                Err(error) => {
+                    if string.is_ok() {
+                        string = Err(error);
+                    }
+                }
+            }
+        }
     }
 
     pub fn push_char(&mut self, value: char) {
         self.push(value as i32);
     }
+
+    /// The stack contents, from bottom to top.
+    pub fn values(&self) -> Vec<i32> {
+        let mut values = vec![];
+        for segment in &self.segments {
+            match segment {
+                Segment::Cells(cells) => values.extend_from_slice(cells),
+                Segment::Zeros(count) => values.resize(values.len() + *count, 0),
+            }
+        }
+        values
+    }
+
+    /// The cell `depth` places below the top, the top being at depth zero,
+    /// or zero past the bottom of the stack.
+    pub fn peek(&self, mut depth: usize) -> i32 {
+        for segment in self.segments.iter().rev() {
+            match segment {
+                Segment::Cells(cells) if depth < cells.len() => {
+                    return cells[cells.len() - 1 - depth]
+                }
+                Segment::Cells(cells) => depth -= cells.len(),
+                Segment::Zeros(count) if depth < *count => return 0,
+                Segment::Zeros(count) => depth -= *count,
+            }
+        }
+        0
+    }
+
+    pub fn len(&self) -> usize {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Cells(cells) => cells.len(),
+                Segment::Zeros(count) => *count,
+            })
+            .fold(0, usize::saturating_add)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&mut self) {
+        *self = Stack::default();
+    }
+
+    /// Pops and drops `count` cells, without going through them one by one
+    /// when they are zeros or missing.
+    pub fn discard(&mut self, count: usize) {
+        self.pop_many(count);
+    }
+
+    /// Pushes `count` zeros, stored as a single run.
+    fn push_zeros(&mut self, count: usize) {
+        self.push_segment(Segment::Zeros(count));
+    }
+
+    fn push_segment(&mut self, segment: Segment) {
+        match segment {
+            Segment::Cells(mut cells) => match self.segments.last_mut() {
+                Some(Segment::Cells(top)) => top.append(&mut cells),
+                _ if cells.is_empty() => (),
+                _ => self.segments.push(Segment::Cells(cells)),
+            },
+            Segment::Zeros(0) => (),
+            Segment::Zeros(count) => match self.segments.last_mut() {
+                Some(Segment::Zeros(top)) => *top = top.saturating_add(count),
+                _ => self.segments.push(Segment::Zeros(count)),
+            },
+        }
+    }
+
+    /// Pops `count` cells, returned as a stack in their original order.
+    /// Missing cells are read as zeros, which are not stored one by one.
+    fn pop_many(&mut self, count: usize) -> Stack {
+        let mut popped = vec![];
+        let mut remaining = count;
+        while remaining > 0 {
+            let single = self.segments.len() == 1;
+            let (segment, emptied) = match self.segments.last_mut() {
+                Some(Segment::Cells(cells)) if !cells.is_empty() => {
+                    let taken = remaining.min(cells.len());
+                    let moved = cells.split_off(cells.len() - taken);
+                    remaining -= taken;
+                    (Segment::Cells(moved), cells.is_empty())
+                }
+                Some(Segment::Zeros(zeros)) => {
+                    let taken = remaining.min(*zeros);
+                    *zeros -= taken;
+                    remaining -= taken;
+                    (Segment::Zeros(taken), *zeros == 0)
+                }
+                _ => {
+                    let taken = remaining;
+                    remaining = 0;
+                    (Segment::Zeros(taken), false)
+                }
+            };
+            if emptied && !single {
+                self.segments.pop();
+            }
+            popped.push(segment);
+        }
+        let mut stack = Stack::default();
+        for segment in popped.into_iter().rev() {
+            stack.push_segment(segment);
+        }
+        stack
+    }
+
+    /// Pushes every cell of `other`, bottom first.
+    fn append(&mut self, other: Stack) {
+        for segment in other.segments {
+            self.push_segment(segment);
+        }
+    }
+
+    /// The same cells in the opposite order.
+    fn reversed(self) -> Stack {
+        let mut stack = Stack::default();
+        for segment in self.segments.into_iter().rev() {
+            stack.push_segment(match segment {
+                Segment::Cells(mut cells) => {
+                    cells.reverse();
+                    Segment::Cells(cells)
+                }
+                zeros => zeros,
+            });
+        }
+        stack
+    }
+}
+
+/// The Funge-98 stack of stacks. It dereferences to the top of stack
+/// (TOSS), so it can be used wherever a single [`Stack`] is expected; the
+/// stack below it is the second on stack (SOSS).
+#[derive(Debug, Clone)]
+pub struct StackStack {
+    stacks: Vec<Stack>,
+}
+
+impl Default for StackStack {
+    fn default() -> Self {
+        StackStack {
+            stacks: vec![Stack::default()],
+        }
+    }
+}
+
+impl StackStack {
+    /// The number of stacks, never less than one.
+    pub fn depth(&self) -> usize {
+        self.stacks.len()
+    }
+
+    /// The stacks, from bottom to top.
+    pub fn stacks(&self) -> &[Stack] {
+        &self.stacks
+    }
+
+    /// Implements `{`: pops a count from the TOSS and pushes a new TOSS.
+    /// A positive count moves that many cells from the SOSS to the new TOSS,
+    /// keeping their order; a negative one pushes that many zeros on the
+    /// SOSS. `offset` is then pushed on the SOSS as a vector.
+    pub fn begin_block(&mut self, offset: (i32, i32)) {
+        let count = self.pop();
+        let toss = if count > 0 {
+            self.pop_many(count as usize)
+        } else {
+            self.push_zeros(count.unsigned_abs() as usize);
+            Stack::default()
+        };
+        self.push(offset.0);
+        self.push(offset.1);
+        self.stacks.push(toss);
+    }
+
+    /// Implements `}`: pops a count from the TOSS, then the storage offset
+    /// from the SOSS, which is returned. A positive count moves that many
+    /// cells from the TOSS to the SOSS, keeping their order; a negative one
+    /// pops that many cells from the SOSS. The TOSS is then discarded.
+    ///
+    /// Returns `None`, leaving the stacks untouched, if there is no SOSS.
+    pub fn end_block(&mut self) -> Option<(i32, i32)> {
+        if self.depth() < 2 {
+            return None;
+        }
+        let count = self.pop();
+        let mut toss = self.stacks.pop().unwrap_or_default();
+        let y = self.pop();
+        let x = self.pop();
+        if count > 0 {
+            let moved = toss.pop_many(count as usize);
+            self.append(moved);
+        } else {
+            self.pop_many(count.unsigned_abs() as usize);
+        }
+        Some((x, y))
+    }
+
+    /// Implements `u`: pops a count from the TOSS. A positive count pops
+    /// that many cells from the SOSS and pushes each on the TOSS, reversing
+    /// their order; a negative one does the same from the TOSS to the SOSS.
+    ///
+    /// Returns `false`, leaving the stacks untouched, if there is no SOSS.
+    pub fn under(&mut self) -> bool {
+        if self.depth() < 2 {
+            return false;
+        }
+        let count = self.pop();
+        let (toss, rest) = self.stacks.split_last_mut().unwrap();
+        let soss = rest.last_mut().unwrap();
+        let (from, to) = if count > 0 {
+            (soss, toss)
+        } else {
+            (toss, soss)
+        };
+        to.append(from.pop_many(count.unsigned_abs() as usize).reversed());
+        true
+    }
+}
+
+impl Deref for StackStack {
+    type Target = Stack;
+
+    fn deref(&self) -> &Stack {
+        self.stacks.last().unwrap()
+    }
+}
+
+impl DerefMut for StackStack {
+    fn deref_mut(&mut self) -> &mut Stack {
+        self.stacks.last_mut().unwrap()
+    }
 }
 
 #[cfg(test)]
@@ -46,14 +334,14 @@ mod tests {
     fn push() {
         let mut stack = new_stack();
         stack.push(1);
-        assert_eq!(1, stack.stack[0]);
+        assert_eq!(vec![1], stack.values());
     }
 
     #[test]
     fn push_char() {
         let mut stack = new_stack();
         stack.push_char('A');
-        assert_eq!(65, stack.stack[0]);
+        assert_eq!(vec![65], stack.values());
     }
 
     #[test]
@@ -63,6 +351,25 @@ mod tests {
         assert_eq!(9, stack.pop());
     }
 
+    #[test]
+    fn values() {
+        let mut stack = new_stack();
+        stack.push(1);
+        stack.push(2);
+        assert_eq!(vec![1, 2], stack.values());
+        assert_eq!(2, stack.len());
+        assert!(!stack.is_empty());
+    }
+
+    #[test]
+    fn clear() {
+        let mut stack = new_stack();
+        stack.push(1);
+        stack.push(2);
+        stack.clear();
+        assert!(stack.is_empty());
+    }
+
     #[test]
     fn pop_empty() {
         let mut stack = new_stack();
@@ -73,18 +380,274 @@ mod tests {
     fn pop_char() {
         let mut stack = new_stack();
         stack.push_char('A');
-        assert_eq!('A', stack.pop_char());
+        assert_eq!('A', stack.pop_char().unwrap());
     }
 
     #[test]
     fn pop_char_from_number() {
         let mut stack = new_stack();
         stack.push(65);
-        assert_eq!('A', stack.pop_char());
+        assert_eq!('A', stack.pop_char().unwrap());
     }
     #[test]
     fn pop_char_empty() {
         let mut stack = new_stack();
-        assert_eq!(char::from(0), stack.pop_char());
+        assert_eq!(char::from(0), stack.pop_char().unwrap());
+    }
+
+    #[test]
+    fn pop_string() {
+        let mut stack = new_stack();
+        stack.push(7);
+        stack.push(0);
+        stack.push_char('b');
+        stack.push_char('a');
+        assert_eq!("ab", stack.pop_string().unwrap());
+        assert_eq!(vec![7], stack.values());
+        stack.pop();
+        assert_eq!("", stack.pop_string().unwrap());
+    }
+
+    #[test]
+    fn pop_string_invalid_character() {
+        let mut stack = new_stack();
+        stack.push(7);
+        stack.push(0);
+        stack.push_char('b');
+        stack.push(-2);
+        stack.push(-1);
+        stack.push_char('a');
+        match stack.pop_string() {
+            Err(InterpreterError::InvalidCharacter(value)) => assert_eq!(-1, value),
+            result => panic!("Unexpected result: {:?}", result),
+        }
+        assert_eq!(vec![7], stack.values());
+    }
+
+    #[test]
+    fn pop_char_negative() {
+        let mut stack = new_stack();
+        stack.push(-1);
+        match stack.pop_char() {
+            Err(InterpreterError::InvalidCharacter(value)) => assert_eq!(-1, value),
+            other => panic!("Unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn pop_char_surrogate() {
+        let mut stack = new_stack();
+        stack.push(0xD800);
+        assert!(stack.pop_char().is_err());
+    }
+
+    fn stack_stack(soss: &[i32], toss: &[i32]) -> StackStack {
+        let mut stacks = StackStack::default();
+        for value in soss {
+            stacks.push(*value);
+        }
+        stacks.push(0);
+        stacks.begin_block((0, 0));
+        stacks.stacks[0].pop();
+        stacks.stacks[0].pop();
+        for value in toss {
+            stacks.push(*value);
+        }
+        stacks
+    }
+
+    #[test]
+    fn stack_stack_default() {
+        let stacks = StackStack::default();
+        assert_eq!(1, stacks.depth());
+        assert!(stacks.is_empty());
+    }
+
+    #[test]
+    fn begin_block() {
+        let mut stacks = StackStack::default();
+        for value in &[1, 2, 3, 2] {
+            stacks.push(*value);
+        }
+        stacks.begin_block((5, 6));
+        assert_eq!(2, stacks.depth());
+        assert_eq!(vec![2, 3], stacks.values());
+        assert_eq!(vec![1, 5, 6], stacks.stacks()[0].values());
+    }
+
+    #[test]
+    fn begin_block_missing_cells() {
+        let mut stacks = StackStack::default();
+        stacks.push(7);
+        stacks.push(3);
+        stacks.begin_block((0, 0));
+        assert_eq!(vec![0, 0, 7], stacks.values());
+    }
+
+    #[test]
+    fn begin_block_negative() {
+        let mut stacks = StackStack::default();
+        stacks.push(9);
+        stacks.push(-2);
+        stacks.begin_block((1, 2));
+        assert!(stacks.is_empty());
+        assert_eq!(vec![9, 0, 0, 1, 2], stacks.stacks()[0].values());
+    }
+
+    #[test]
+    fn end_block() {
+        let mut stacks = StackStack::default();
+        stacks.push(1);
+        stacks.push(0);
+        stacks.begin_block((5, 6));
+        stacks.push(7);
+        stacks.push(8);
+        stacks.push(2);
+        assert_eq!(Some((5, 6)), stacks.end_block());
+        assert_eq!(1, stacks.depth());
+        assert_eq!(vec![1, 7, 8], stacks.values());
+    }
+
+    #[test]
+    fn end_block_negative() {
+        let mut stacks = StackStack::default();
+        stacks.push(1);
+        stacks.push(2);
+        stacks.push(0);
+        stacks.begin_block((5, 6));
+        stacks.push(-1);
+        assert_eq!(Some((5, 6)), stacks.end_block());
+        assert_eq!(vec![1], stacks.values());
+    }
+
+    #[test]
+    fn end_block_single_stack() {
+        let mut stacks = StackStack::default();
+        stacks.push(3);
+        assert_eq!(None, stacks.end_block());
+        assert_eq!(vec![3], stacks.values());
+    }
+
+    #[test]
+    fn under() {
+        let mut stacks = stack_stack(&[1, 2, 3], &[2]);
+        assert!(stacks.under());
+        assert_eq!(vec![3, 2], stacks.values());
+        assert_eq!(vec![1], stacks.stacks()[0].values());
+    }
+
+    #[test]
+    fn under_negative() {
+        let mut stacks = stack_stack(&[1], &[4, 5, -2]);
+        assert!(stacks.under());
+        assert!(stacks.is_empty());
+        assert_eq!(vec![1, 5, 4], stacks.stacks()[0].values());
+    }
+
+    #[test]
+    fn under_single_stack() {
+        let mut stacks = StackStack::default();
+        stacks.push(1);
+        assert!(!stacks.under());
+        assert_eq!(vec![1], stacks.values());
+    }
+
+    #[test]
+    fn discard() {
+        let mut stack = new_stack();
+        stack.push(1);
+        stack.push(2);
+        stack.push(3);
+        stack.discard(2);
+        assert_eq!(vec![1], stack.values());
+        stack.discard(usize::MAX);
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn peek() {
+        let mut stack = new_stack();
+        stack.push(1);
+        stack.push(2);
+        assert_eq!(2, stack.peek(0));
+        assert_eq!(1, stack.peek(1));
+        assert_eq!(0, stack.peek(2));
+    }
+
+    #[test]
+    fn zero_runs() {
+        let mut stack = new_stack();
+        stack.push(1);
+        stack.push_zeros(2);
+        stack.push(3);
+        assert_eq!(vec![1, 0, 0, 3], stack.values());
+        assert_eq!(4, stack.len());
+        assert_eq!(0, stack.peek(2));
+        assert_eq!(1, stack.peek(3));
+        assert_eq!(3, stack.pop());
+        assert_eq!(0, stack.pop());
+        assert_eq!(0, stack.pop());
+        assert_eq!(vec![1], stack.values());
+        assert_eq!(1, stack.pop());
+        assert!(stack.is_empty());
+    }
+
+    #[test]
+    fn begin_block_huge() {
+        let mut stacks = StackStack::default();
+        stacks.push(7);
+        stacks.push(i32::MAX);
+        stacks.begin_block((0, 0));
+        assert_eq!(i32::MAX as usize, stacks.len());
+        assert_eq!(7, stacks.pop());
+        assert_eq!(0, stacks.pop());
+    }
+
+    #[test]
+    fn begin_block_huge_negative() {
+        let mut stacks = StackStack::default();
+        stacks.push(9);
+        stacks.push(i32::MIN);
+        stacks.begin_block((1, 2));
+        assert_eq!(
+            i32::MIN.unsigned_abs() as usize + 3,
+            stacks.stacks()[0].len()
+        );
+        assert_eq!(2, stacks.stacks()[0].peek(0));
+        assert_eq!(0, stacks.stacks()[0].peek(2));
+    }
+
+    #[test]
+    fn end_block_huge() {
+        let mut stacks = stack_stack(&[1, 2], &[5, i32::MAX]);
+        stacks.stacks[0].push(0);
+        stacks.stacks[0].push(0);
+        assert_eq!(Some((0, 0)), stacks.end_block());
+        assert_eq!(i32::MAX as usize + 2, stacks.len());
+        assert_eq!(5, stacks.pop());
+        assert_eq!(0, stacks.pop());
+        let mut stacks = stack_stack(&[1, 2], &[i32::MIN]);
+        stacks.stacks[0].push(0);
+        stacks.stacks[0].push(0);
+        assert_eq!(Some((0, 0)), stacks.end_block());
+        assert!(stacks.is_empty());
+    }
+
+    #[test]
+    fn under_huge() {
+        let mut stacks = stack_stack(&[1, 2], &[i32::MAX]);
+        assert!(stacks.under());
+        assert!(stacks.stacks()[0].is_empty());
+        assert_eq!(i32::MAX as usize, stacks.len());
+        assert_eq!(0, stacks.peek(0));
+        assert_eq!(1, stacks.peek(i32::MAX as usize - 2));
+        assert_eq!(2, stacks.peek(i32::MAX as usize - 1));
+        let mut stacks = stack_stack(&[1], &[4, i32::MIN]);
+        assert!(stacks.under());
+        assert!(stacks.is_empty());
+        let soss = &stacks.stacks()[0];
+        assert_eq!(i32::MIN.unsigned_abs() as usize + 1, soss.len());
+        assert_eq!(0, soss.peek(0));
+        assert_eq!(4, soss.peek(soss.len() - 2));
     }
 }